@@ -1 +1,5 @@
-rust_bundler_cp --input . >output.rs 
+#!/bin/sh
+# Один файл для referee: собирается без фич, окно (viewer) и профайлер вырезаются cfg
+set -e
+cargo build --release --no-default-features
+rust_bundler_cp --input . >output.rs
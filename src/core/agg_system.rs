@@ -1,23 +1,178 @@
 use crate::{
-    core::{ai_system::AiSystem, cover_system::CoverSystem},
-    data::{game_context::GameContext, hero::HeroAction},
+    core::{
+        ai_system::AiSystem,
+        cover_system::CoverSystem,
+        endgame_system::EndgameSystem,
+        event_system::{self, EventSystem},
+        inference_system::InferenceSystem,
+        opponent_model::OpponentModel,
+        predict_system::Score,
+        rationale::{Candidate, Rationale},
+        state_machine::{State, StateMachine},
+    },
+    data::{
+        game_context::GameContext,
+        hero::{HeroAction, HeroActionVariant},
+    },
+    infra::{
+        logger,
+        profiler::Profiler,
+        storage::{Key, Storage},
+        turn_clock::TurnClock,
+    },
 };
 
+/// Состояние прошлого хода
+pub const LAST_CONTEXT: Key<GameContext> = Key::new("last_context");
+/// Наши команды прошлого хода, для проверки рассинхрона
+pub const LAST_ACTIONS: Key<Vec<HeroAction>> = Key::new("last_actions");
+/// Статистика поведения врагов
+pub const OPPONENTS: Key<OpponentModel> = Key::new("opponents");
+/// Очки, набранные сторонами за прошедшие ходы
+pub const SCORE: Key<Score> = Key::new("score");
+
 pub struct AggSystem {
     ai: AiSystem,
     cover: CoverSystem,
+    endgame: EndgameSystem,
+    events: EventSystem,
+    states: StateMachine,
+    /// Почему герои получили команды последнего хода
+    rationale: Vec<Rationale>,
+    storage: Storage,
 }
 
 impl AggSystem {
     pub fn new() -> AggSystem {
+        let mut events = EventSystem::new();
+        events.subscribe(event_system::log_subscriber);
+
         AggSystem {
             ai: AiSystem::new(),
             cover: CoverSystem::new(),
+            endgame: EndgameSystem::new(),
+            events,
+            states: StateMachine::new(),
+            rationale: vec![],
+            storage: Storage::new(),
+        }
+    }
+
+    pub fn events(&self) -> &EventSystem {
+        &self.events
+    }
+
+    pub fn events_mut(&mut self) -> &mut EventSystem {
+        &mut self.events
+    }
+
+    pub fn rationale(&self) -> &[Rationale] {
+        &self.rationale
+    }
+
+    pub fn storage(&self) -> &Storage {
+        &self.storage
+    }
+
+    /// Всегда возвращает команду каждому нашему герою: если время хода
+    /// кончилось, недодуманные герои получают запасное действие.
+    pub fn process(&mut self, ctx: &GameContext, clock: &TurnClock) -> Vec<HeroAction> {
+        let _prof = Profiler::record("AggSystem::process");
+        let prev = self.storage.remove(LAST_CONTEXT);
+
+        if let (Some(prev), Some(actions)) = (&prev, self.storage.get(LAST_ACTIONS)) {
+            let desyncs = InferenceSystem::check_actions(prev, ctx, actions);
+            if !desyncs.is_empty() {
+                logger::log(&desyncs, "AggSystem::process:desync");
+            }
+        }
+
+        // вывод действий врагов общий для событий и модели противника
+        let inferred = prev
+            .as_ref()
+            .map(|prev| {
+                let ours = self
+                    .storage
+                    .get(LAST_ACTIONS)
+                    .map_or(&[][..], |x| x.as_slice());
+                InferenceSystem::infer(prev, ctx, ours)
+            })
+            .unwrap_or_default();
+        if let Some(prev) = &prev {
+            self.events.publish(EventSystem::diff(prev, ctx, &inferred));
         }
+
+        self.storage
+            .entry(OPPONENTS)
+            .update(prev.as_ref(), ctx, &inferred);
+        // первый вход — расстановка до любого хода, очков за неё нет
+        if prev.is_some() {
+            self.storage.entry(SCORE).add_turn(ctx);
+        }
+        let score = *self.storage.entry(SCORE);
+        let opponents = self.storage.entry(OPPONENTS);
+
+        let (mut actions, mut rationale) = if clock.is_expired() {
+            (vec![], vec![])
+        } else if EndgameSystem::is_endgame(ctx) {
+            let actions = self.endgame.solve(ctx, &score, clock).unwrap_or_default();
+            (actions, self.endgame.rationale().to_vec())
+        } else {
+            let actions = self.ai.process(ctx, opponents, clock);
+            (actions, self.ai.rationale().to_vec())
+        };
+
+        // время могло кончиться и посреди решения системы
+        let reason = if clock.is_expired() {
+            "timeout"
+        } else {
+            "no action from system"
+        };
+        for fallback in Self::fallback(ctx) {
+            if !actions.iter().any(|x| x.0 == fallback.0) {
+                logger::log(&(fallback.0, reason), "AggSystem::process:fallback");
+                rationale.retain(|x| x.agent_id != fallback.0);
+                rationale.push(Rationale {
+                    agent_id: fallback.0,
+                    state: State::Fallback,
+                    system: "AggSystem::fallback",
+                    inputs: vec![],
+                    candidates: vec![Candidate {
+                        actions: fallback.1.clone(),
+                        features: vec![],
+                        chosen: true,
+                    }],
+                });
+                actions.push(fallback);
+            }
+        }
+
+        for reason in &rationale {
+            if let Some(transition) = self.states.set(reason.agent_id, reason.state) {
+                logger::info(
+                    format!(
+                        "{}: {:?} -> {:?}",
+                        transition.agent_id, transition.from, transition.to
+                    ),
+                    "AggSystem::process:state",
+                );
+            }
+        }
+        self.rationale = rationale;
+
+        self.storage.insert(LAST_CONTEXT, ctx.clone());
+        self.storage.insert(LAST_ACTIONS, actions.clone());
+        actions
     }
+}
 
-    pub fn process(&mut self, ctx: &GameContext) -> Vec<HeroAction> {
-        return self.ai.process(ctx);
+impl AggSystem {
+    /// Действие, которое не требует расчётов: все прячутся.
+    fn fallback(ctx: &GameContext) -> Vec<HeroAction> {
+        ctx.hero_store
+            .my_heroes()
+            .map(|hero| HeroAction(hero.agent_id, vec![HeroActionVariant::HunkerDown]))
+            .collect()
     }
 }
 
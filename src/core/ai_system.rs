@@ -1,18 +1,34 @@
 use crate::{
-    core::{predict_system::PredictSystem, shooter_system::ShooterSystem},
+    core::{
+        formation_system::FormationSystem,
+        opponent_model::OpponentModel,
+        predict_system::PredictSystem,
+        rationale::{Candidate, Rationale},
+        shooter_system::ShooterSystem,
+        state_machine::State,
+    },
     data::{
+        bitboard::BitBoard,
         game_context::GameContext,
         hero::{Hero, HeroAction, HeroActionVariant},
         position::Position,
         tile::TileView,
     },
-    infra::{logger, lru::LruCache, pathfinder, position_utils::find_cover_position},
+    infra::{
+        logger, lru::LruCache, position_utils::find_cover_position, profiler::Profiler,
+        turn_clock::TurnClock, zobrist,
+    },
 };
 
+/// Ожидаемый входящий урон, при котором герой ещё продвигается к центру
+const ADVANCE_THREAT: f32 = 8.0;
+
 #[derive(Debug, Default)]
 pub struct AiSystem {
     tile_cache: LruCache<&'static str, Vec<TileView>>,
     hero_cache: LruCache<&'static str, Vec<Hero>>,
+    /// Обоснование решений последнего хода
+    rationale: Vec<Rationale>,
 }
 
 impl AiSystem {
@@ -20,12 +36,27 @@ impl AiSystem {
         AiSystem {
             tile_cache: LruCache::new(10),
             hero_cache: LruCache::new(10),
+            rationale: vec![],
         }
     }
-    pub fn process(&mut self, ctx: &GameContext) -> Vec<HeroAction> {
+
+    pub fn rationale(&self) -> &[Rationale] {
+        &self.rationale
+    }
+
+    pub fn process(
+        &mut self,
+        ctx: &GameContext,
+        opponents: &OpponentModel,
+        clock: &TurnClock,
+    ) -> Vec<HeroAction> {
+        let _prof = Profiler::record("AiSystem::process");
         logger::log("", "AiSystem::process");
 
-        if self.tile_cache.get(&"cover_tiles").is_none() {
+        // укрытия зависят от позиций всех героев: сменились — пересчитываем
+        let generation = zobrist::positions_hash(ctx);
+
+        if self.tile_cache.get(&"cover_tiles", generation).is_none() {
             logger::log("", "AiSystem::process:find_cover_position");
             let covered = find_cover_position(ctx, 1);
             self.tile_cache.put(
@@ -34,32 +65,49 @@ impl AiSystem {
                     .iter()
                     .map(|tile| ctx.tilemap.get_tile(tile).unwrap().clone())
                     .collect(),
+                generation,
             );
         }
+        logger::log(&self.tile_cache.stats(), "AiSystem::process:tile_cache");
 
         let (my_score, enemy_score) = PredictSystem::predict(ctx);
 
         let is_enemy_winner = my_score < enemy_score;
 
-        let covers = self.tile_cache.get(&"cover_tiles").unwrap();
-        let mut filtered: Vec<&TileView> = covers.iter().filter(|x| x.is_free()).collect();
+        let covers = self.tile_cache.get(&"cover_tiles", generation).unwrap();
+        let cover_board = BitBoard::from_positions(
+            ctx.tilemap.get_width(),
+            ctx.tilemap.get_height(),
+            covers.iter().map(|x| &x.position),
+        );
+        let formation = FormationSystem::plan(ctx, &cover_board);
 
         let mut hero_actions = vec![];
+        self.rationale.clear();
 
         let limit = 2;
 
-        for hero in ctx.hero_store.heroes.iter().filter(|x| x.is_owner) {
+        for hero in ctx.hero_store.my_heroes() {
+            // остальных героев AggSystem доведёт запасным действием
+            if clock.is_expired() {
+                break;
+            }
             let mut inner_actions = vec![];
-
-            // то ищем более агресивную позицию
+            let planned = formation.iter().find(|x| x.agent_id == hero.agent_id);
+            let step = planned.and_then(|x| x.step);
+            let threat = opponents.expected_threat(ctx, hero);
+            let state = if is_enemy_winner {
+                State::Contest
+            } else if threat < ADVANCE_THREAT {
+                State::Advance
+            } else {
+                State::Hold
+            };
+
+            // проигрываем по территории — занимаем позицию построения даже под угрозой
             if is_enemy_winner {
-                let target = filtered
-                    .iter()
-                    .max_by_key(|tile| tile.position.distance(&hero.position));
-
-                if let Some(t_tile) = target {
-                    inner_actions.push(HeroActionVariant::Move(t_tile.position));
-                    filtered.retain(|t| t.position == t_tile.position);
+                if let Some(step) = step {
+                    inner_actions.push(HeroActionVariant::Move(step));
                 }
             }
 
@@ -86,24 +134,9 @@ impl AiSystem {
                 }
             }
 
-            if ctx
-                .hero_store
-                .heroes
-                .iter()
-                .filter(|x| !x.is_owner)
-                .all(|x| x.cooldown > 0 || x.optimal_range < hero.position.distance(&x.position))
-            {
-                let mut clone_position = hero.position.clone();
-
-                clone_position.x = ctx.tilemap.get_width() / 2;
-
-                let center = pathfinder::find_path(ctx, &hero.position, &clone_position);
-
-                if let Some(values) = center {
-                    for point in values.iter().skip(1) {
-                        inner_actions.push(HeroActionVariant::Move(point.clone()));
-                        break;
-                    }
+            if state == State::Advance {
+                if let Some(step) = step {
+                    inner_actions.push(HeroActionVariant::Move(step));
                 }
             }
 
@@ -113,6 +146,20 @@ impl AiSystem {
                 });
             }
 
+            self.rationale.push(Self::explain(
+                ctx,
+                hero,
+                state,
+                vec![
+                    ("my_score", my_score as f32),
+                    ("enemy_score", enemy_score as f32),
+                    ("threat", threat),
+                    ("advance_threat", ADVANCE_THREAT),
+                ],
+                planned.map(|x| x.features.as_slice()),
+                step,
+                &inner_actions,
+            ));
             hero_actions.push(HeroAction(hero.agent_id, inner_actions));
         }
 
@@ -121,4 +168,59 @@ impl AiSystem {
         // проверить что каждый герой защищен
         // выбрать одного героя который будет продвигаться
     }
+
+    /// Шаг построения и цели в радиусе выстрела; выбранными помечаются те, что попали в команду.
+    fn explain(
+        ctx: &GameContext,
+        hero: &Hero,
+        state: State,
+        inputs: Vec<(&'static str, f32)>,
+        formation: Option<&[(&'static str, i32)]>,
+        step: Option<Position>,
+        chosen: &[HeroActionVariant],
+    ) -> Rationale {
+        let mut candidates = vec![];
+        if let Some(step) = step {
+            let action = HeroActionVariant::Move(step);
+            candidates.push(Candidate {
+                chosen: chosen.contains(&action),
+                actions: vec![action],
+                features: formation
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|(name, value)| (*name, *value as f32))
+                    .collect(),
+            });
+        }
+        // как в ShooterSystem::find_enemy: в радиусе, ближайший лучше
+        for enemy in ctx
+            .hero_store
+            .opponents_of(hero)
+            .filter(|x| x.position.distance_8x(&hero.position) < hero.optimal_range)
+        {
+            let action = HeroActionVariant::Shoot { id: enemy.agent_id };
+            candidates.push(Candidate {
+                chosen: chosen.contains(&action),
+                actions: vec![action],
+                features: vec![("distance", -enemy.position.distance(&hero.position) as f32)],
+            });
+        }
+        for action in chosen {
+            if !candidates.iter().any(|x| x.actions.contains(action)) {
+                candidates.push(Candidate {
+                    actions: vec![action.clone()],
+                    features: vec![],
+                    chosen: true,
+                });
+            }
+        }
+
+        Rationale {
+            agent_id: hero.agent_id,
+            state,
+            system: "AiSystem",
+            inputs,
+            candidates,
+        }
+    }
 }
@@ -0,0 +1,438 @@
+use std::collections::HashMap;
+
+use crate::{
+    core::{
+        predict_system::{PredictSystem, Score, MAX_TURNS},
+        rationale::{Candidate, Rationale},
+        state_machine::State,
+    },
+    data::{
+        bitboard::BitBoard,
+        game_context::GameContext,
+        hero::{HeroAction, HeroActionVariant, MAX_WETNESS, SPLASH_DAMAGE, THROW_RANGE},
+        position::Position,
+        sim_state::{SimHero, SimState},
+    },
+    infra::{logger, profiler::Profiler, simulator, turn_clock::TurnClock},
+};
+
+/// Эндшпиль: у каждой стороны осталось не больше стольких героев
+pub const ENDGAME_HEROES: usize = 2;
+/// Предел итеративного углубления (ходов)
+pub const MAX_DEPTH: u32 = 4;
+/// Размер таблицы транспозиций, дальше новые позиции не запоминаются
+pub const TABLE_LIMIT: usize = 200_000;
+
+const WIN: i32 = 1_000_000;
+
+/// Чего добивается перебор; задаёт веса оценки.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndgameMode {
+    /// Набранных очков и перевеса по территории хватает до конца игры: бережём героев
+    Turtle,
+    /// Врага можно добить
+    Chase,
+    /// Отстаём: набираем территорию, пока не кончились ходы
+    Race,
+}
+
+impl EndgameMode {
+    /// Веса (территория, урон по врагу, урон по нам).
+    fn weights(&self) -> (i32, i32, i32) {
+        match self {
+            EndgameMode::Turtle => (1, 1, 4),
+            EndgameMode::Chase => (1, 4, 1),
+            EndgameMode::Race => (4, 1, 1),
+        }
+    }
+}
+
+/// Перебор одновременных ходов (максимин) на `SimState`, когда героев мало.
+#[derive(Debug, Default)]
+pub struct EndgameSystem {
+    /// (хеш состояния, оставшаяся глубина) -> оценка
+    table: HashMap<(u64, u32), i32>,
+    /// Обоснование решений последнего хода
+    rationale: Vec<Rationale>,
+}
+
+impl EndgameSystem {
+    pub fn new() -> Self {
+        Self {
+            table: HashMap::new(),
+            rationale: vec![],
+        }
+    }
+
+    pub fn rationale(&self) -> &[Rationale] {
+        &self.rationale
+    }
+
+    pub fn is_endgame(ctx: &GameContext) -> bool {
+        let mine = ctx.hero_store.my_heroes().count();
+        let theirs = ctx.hero_store.enemies().count();
+        mine > 0 && theirs > 0 && mine <= ENDGAME_HEROES && theirs <= ENDGAME_HEROES
+    }
+
+    pub fn mode(ctx: &GameContext, score: &Score, turns_left: u32) -> EndgameMode {
+        if PredictSystem::projected_final_lead(ctx, score, turns_left) > 0 {
+            return EndgameMode::Turtle;
+        }
+        // весь урон наших героев за один ход
+        let burst: i32 = ctx
+            .hero_store
+            .my_heroes()
+            .map(|x| x.soaking_power + if x.splash_bombs > 0 { SPLASH_DAMAGE } else { 0 })
+            .sum();
+        if ctx
+            .hero_store
+            .enemies()
+            .any(|x| x.wetness + burst >= MAX_WETNESS)
+        {
+            EndgameMode::Chase
+        } else {
+            EndgameMode::Race
+        }
+    }
+
+    /// Лучшие действия наших героев; None, если не успели досчитать даже один ход.
+    pub fn solve(
+        &mut self,
+        ctx: &GameContext,
+        score: &Score,
+        clock: &TurnClock,
+    ) -> Option<Vec<HeroAction>> {
+        let _prof = Profiler::record("EndgameSystem::solve");
+        let turns_left = MAX_TURNS.saturating_sub(clock.turn()).max(1);
+        let mode = Self::mode(ctx, score, turns_left);
+        // оценки зависят от режима, поэтому таблица живёт один ход
+        self.table.clear();
+
+        let root = match SimState::from_context(ctx) {
+            Ok(root) => root,
+            Err(err) => {
+                logger::error(format!("{}", err), "EndgameSystem::solve");
+                return None;
+            }
+        };
+        let ours = joint_actions(&root, true);
+        // оценки вариантов на последней досчитанной глубине
+        let mut values = None;
+        let mut reached = 0;
+
+        for depth in 1..=MAX_DEPTH.min(turns_left) {
+            let Some(found) = self.search_root(&root, &ours, depth, mode, clock) else {
+                break;
+            };
+            values = Some(found);
+            reached = depth;
+        }
+
+        // первый из лучших, как при переборе по порядку
+        let best = values.as_ref().and_then(|values| {
+            let top = values.iter().map(|x| x.0).max()?;
+            values.iter().position(|x| x.0 == top)
+        });
+        logger::log(
+            &(
+                mode,
+                best.map(|index| values.as_ref().unwrap()[index].0),
+                self.table.len(),
+            ),
+            "EndgameSystem::solve",
+        );
+
+        self.rationale.clear();
+        let (Some(values), Some(best)) = (values, best) else {
+            return None;
+        };
+        let inputs = vec![
+            ("depth", reached as f32),
+            ("turns_left", turns_left as f32),
+            ("score_lead", score.lead() as f32),
+            (
+                "final_lead",
+                PredictSystem::projected_final_lead(ctx, score, turns_left) as f32,
+            ),
+        ];
+        for hero in ctx.hero_store.my_heroes() {
+            self.rationale.push(explain(
+                hero.agent_id,
+                State::Endgame(mode),
+                inputs.clone(),
+                &ours,
+                &values,
+                best,
+            ));
+        }
+        Some(ours[best].clone())
+    }
+
+    /// Оценки всех наших вариантов с признаком точности: отсечённые варианты
+    /// (не лучше уже найденного) получают только верхнюю границу оценки.
+    fn search_root(
+        &mut self,
+        root: &SimState,
+        ours: &[Vec<HeroAction>],
+        depth: u32,
+        mode: EndgameMode,
+        clock: &TurnClock,
+    ) -> Option<Vec<(i32, bool)>> {
+        let mut best = -WIN * 2;
+        let mut values = Vec::with_capacity(ours.len());
+        for actions in ours {
+            let value = self.reply(root, actions, depth, best, mode, clock)?;
+            values.push((value, value > best));
+            best = best.max(value);
+        }
+        Some(values)
+    }
+
+    /// Худший для нас ответ врага на `ours`; перебор прекращается,
+    /// как только ответ не лучше уже найденного `alpha`.
+    fn reply(
+        &mut self,
+        state: &SimState,
+        ours: &[HeroAction],
+        depth: u32,
+        alpha: i32,
+        mode: EndgameMode,
+        clock: &TurnClock,
+    ) -> Option<i32> {
+        let mut worst = WIN * 2;
+        for theirs in joint_actions(state, false) {
+            if clock.is_expired() {
+                return None;
+            }
+            let mut next = state.clone();
+            let actions: Vec<HeroAction> = ours.iter().chain(theirs.iter()).cloned().collect();
+            simulator::simulate_state(&mut next, &actions);
+
+            worst = worst.min(self.value(&next, depth - 1, mode, clock)?);
+            if worst <= alpha {
+                break;
+            }
+        }
+        Some(worst)
+    }
+
+    fn value(
+        &mut self,
+        state: &SimState,
+        depth: u32,
+        mode: EndgameMode,
+        clock: &TurnClock,
+    ) -> Option<i32> {
+        if depth == 0 || is_decided(state) {
+            return Some(evaluate(state, mode));
+        }
+        if let Some(value) = self.table.get(&(state.hash, depth)) {
+            return Some(*value);
+        }
+
+        let mut best = -WIN * 2;
+        for ours in joint_actions(state, true) {
+            best = best.max(self.reply(state, &ours, depth, best, mode, clock)?);
+        }
+        if self.table.len() < TABLE_LIMIT {
+            self.table.insert((state.hash, depth), best);
+        }
+        Some(best)
+    }
+}
+
+/// Варианты одного героя из совместных: лучшая оценка среди сочетаний с этим вариантом.
+/// Оценки отсечённых вариантов показываются как верхние границы.
+fn explain(
+    agent_id: i32,
+    state: State,
+    inputs: Vec<(&'static str, f32)>,
+    ours: &[Vec<HeroAction>],
+    values: &[(i32, bool)],
+    best: usize,
+) -> Rationale {
+    let own = |actions: &[HeroAction]| {
+        actions
+            .iter()
+            .find(|x| x.0 == agent_id)
+            .map(|x| x.1.clone())
+            .unwrap_or_default()
+    };
+    let chosen = own(&ours[best]);
+
+    let mut candidates: Vec<Candidate> = vec![];
+    for (actions, (value, exact)) in ours.iter().zip(values) {
+        let actions = own(actions);
+        let feature = (if *exact { "maximin" } else { "maximin<=" }, *value as f32);
+        match candidates.iter_mut().find(|x| x.actions == actions) {
+            Some(found) if found.features[0].1 < feature.1 => found.features[0] = feature,
+            Some(_) => {}
+            None => candidates.push(Candidate {
+                chosen: actions == chosen,
+                actions,
+                features: vec![feature],
+            }),
+        }
+    }
+
+    Rationale {
+        agent_id,
+        state,
+        system: "EndgameSystem",
+        inputs,
+        candidates,
+    }
+}
+
+fn is_decided(state: &SimState) -> bool {
+    !state.alive().any(|x| x.is_owner) || !state.alive().any(|x| !x.is_owner)
+}
+
+/// Оценка с нашей стороны: выбывание стороны решает всё, иначе территория и мокрость.
+fn evaluate(state: &SimState, mode: EndgameMode) -> i32 {
+    let mine = state.alive().any(|x| x.is_owner);
+    let theirs = state.alive().any(|x| !x.is_owner);
+    match (mine, theirs) {
+        (false, false) => return 0,
+        (false, true) => return -WIN,
+        (true, false) => return WIN,
+        (true, true) => {}
+    }
+
+    let side = |owner: bool| {
+        let mut board = BitBoard::empty(state.map.width, state.map.height);
+        for hero in state.alive().filter(|x| x.is_owner == owner) {
+            board.set(&hero.position());
+        }
+        board
+    };
+    let (territory, enemy_territory) = PredictSystem::territory_from(side(true), side(false));
+    let lead = territory.count() as i32 - enemy_territory.count() as i32;
+
+    // выбывший герой стоит больше, чем полностью промокший
+    let soaked = |owner: bool| -> i32 {
+        state
+            .heroes()
+            .iter()
+            .filter(|x| x.is_owner == owner)
+            .map(|x| {
+                if x.alive {
+                    x.wetness as i32
+                } else {
+                    MAX_WETNESS * 2
+                }
+            })
+            .sum()
+    };
+
+    let (territory_weight, dealt_weight, taken_weight) = mode.weights();
+    lead * territory_weight + soaked(false) * dealt_weight - soaked(true) * taken_weight
+}
+
+/// Все сочетания вариантов героев одной стороны.
+fn joint_actions(state: &SimState, owner: bool) -> Vec<Vec<HeroAction>> {
+    let mut joint = vec![vec![]];
+    for hero in state.alive().filter(|x| x.is_owner == owner) {
+        let options = hero_options(state, hero);
+        joint = joint
+            .iter()
+            .flat_map(|prefix: &Vec<HeroAction>| {
+                options.iter().map(move |option| {
+                    let mut actions = prefix.clone();
+                    actions.push(HeroAction(hero.agent_id, option.clone()));
+                    actions
+                })
+            })
+            .collect();
+    }
+    joint
+}
+
+/// Стоять или шагнуть в соседнюю клетку, и лучшая атака с новой позиции (иначе укрыться).
+fn hero_options(state: &SimState, hero: &SimHero) -> Vec<Vec<HeroActionVariant>> {
+    let from = hero.position();
+    let mut positions = vec![from];
+    for (dx, dy) in Position::DIRECTIONS {
+        let (x, y) = (from.x as i32 + dx, from.y as i32 + dy);
+        if state.map.out_of_bounds(x, y) {
+            continue;
+        }
+        let next = Position {
+            x: x as usize,
+            y: y as usize,
+        };
+        if state.is_free(&next) {
+            positions.push(next);
+        }
+    }
+
+    positions
+        .into_iter()
+        .map(|position| {
+            let mut variants = vec![];
+            if position != from {
+                variants.push(HeroActionVariant::Move(position));
+            }
+            variants
+                .push(best_attack(state, hero, &position).unwrap_or(HeroActionVariant::HunkerDown));
+            variants
+        })
+        .collect()
+}
+
+/// Выстрел или бомба с наибольшим уроном по врагам из `from`.
+fn best_attack(state: &SimState, hero: &SimHero, from: &Position) -> Option<HeroActionVariant> {
+    let enemies: Vec<SimHero> = state
+        .alive()
+        .filter(|x| x.player != hero.player)
+        .copied()
+        .collect();
+
+    let shot = enemies
+        .iter()
+        .filter(|_| hero.cooldown == 0)
+        .map(|target| {
+            let damage = simulator::shot_damage(
+                |x, y| state.map.tile_at(x, y),
+                hero.soaking_power as i32,
+                hero.optimal_range as i32,
+                from,
+                &target.position(),
+            );
+            (damage, target.agent_id)
+        })
+        .filter(|(damage, _)| *damage > 0)
+        .max_by_key(|(damage, _)| *damage);
+
+    let throw = enemies
+        .iter()
+        .filter(|_| hero.splash_bombs > 0)
+        .map(|x| x.position())
+        .filter(|target| target.distance(from) <= THROW_RANGE)
+        // по своим не бросаем; сам бросающий уже стоит в `from`
+        .filter(|target| {
+            from.distance_8x(target) > 1
+                && !state.alive().any(|x| {
+                    x.agent_id != hero.agent_id
+                        && x.player == hero.player
+                        && x.position().distance_8x(target) <= 1
+                })
+        })
+        .map(|target| {
+            let hits = enemies
+                .iter()
+                .filter(|x| x.position().distance_8x(&target) <= 1)
+                .count() as i32;
+            (hits * SPLASH_DAMAGE, target)
+        })
+        .max_by_key(|(damage, _)| *damage);
+
+    match (shot, throw) {
+        (Some((shot, _)), Some((splash, target))) if splash > shot => {
+            Some(HeroActionVariant::Throw(target))
+        }
+        (Some((_, id)), _) => Some(HeroActionVariant::Shoot { id }),
+        (None, Some((_, target))) => Some(HeroActionVariant::Throw(target)),
+        (None, None) => None,
+    }
+}
@@ -0,0 +1,163 @@
+use crate::{
+    core::{inference_system::InferredAction, predict_system::PredictSystem},
+    data::{
+        game_context::GameContext,
+        game_event::GameEvent,
+        hero::{SPLASH_DAMAGE, THROW_RANGE},
+        position::Position,
+    },
+    infra::{logger, profiler::Profiler},
+};
+
+type Subscriber = Box<dyn FnMut(&GameEvent)>;
+
+/// Поток событий хода: строит события по разнице состояний и раздаёт подписчикам.
+///
+/// Состояния могут прийти как от referee, так и из локального симулятора.
+pub struct EventSystem {
+    subscribers: Vec<Subscriber>,
+    last: Vec<GameEvent>,
+}
+
+impl EventSystem {
+    pub fn new() -> EventSystem {
+        EventSystem {
+            subscribers: vec![],
+            last: vec![],
+        }
+    }
+
+    pub fn subscribe<F: FnMut(&GameEvent) + 'static>(&mut self, subscriber: F) {
+        self.subscribers.push(Box::new(subscriber));
+    }
+
+    /// Раздаёт события подписчикам и запоминает их как события последнего хода.
+    pub fn publish(&mut self, events: Vec<GameEvent>) {
+        for event in &events {
+            for subscriber in self.subscribers.iter_mut() {
+                subscriber(event);
+            }
+        }
+        self.last = events;
+    }
+
+    /// События последнего опубликованного хода.
+    pub fn last(&self) -> &[GameEvent] {
+        &self.last
+    }
+
+    /// События между `prev` и `cur`; выстрелы и их цели берутся только
+    /// из `inferred` (`InferenceSystem::infer` для той же пары ходов).
+    pub fn diff(
+        prev: &GameContext,
+        cur: &GameContext,
+        inferred: &[InferredAction],
+    ) -> Vec<GameEvent> {
+        let _prof = Profiler::record("EventSystem::diff");
+        let mut events = vec![];
+
+        for before in prev.hero_store.alive() {
+            let Some(after) = cur.hero_store.get(before.agent_id) else {
+                continue;
+            };
+            let agent_id = before.agent_id;
+
+            if after.position != before.position {
+                events.push(GameEvent::HeroMoved {
+                    agent_id,
+                    from: before.position,
+                    to: after.position,
+                });
+            }
+            let shot = inferred.iter().find_map(|x| match x {
+                InferredAction::Shoot {
+                    agent_id: id,
+                    target,
+                } if *id == agent_id => Some(*target),
+                _ => None,
+            });
+            if let Some(target) = shot {
+                events.push(GameEvent::HeroShot { agent_id, target });
+            }
+            if after.splash_bombs < before.splash_bombs {
+                let (position, hits) = bomb_landing(prev, cur, &after.position);
+                events.push(GameEvent::BombLanded {
+                    agent_id,
+                    position,
+                    hits,
+                });
+            }
+            if !after.alive {
+                events.push(GameEvent::HeroEliminated {
+                    agent_id,
+                    position: after.position,
+                });
+            } else if before.cooldown > 0 && after.cooldown == 0 {
+                events.push(GameEvent::CooldownReady { agent_id });
+            }
+        }
+
+        let (mine_before, theirs_before) = PredictSystem::territory(prev);
+        let (mine, theirs) = PredictSystem::territory(cur);
+        if mine.count() != mine_before.count() || theirs.count() != theirs_before.count() {
+            events.push(GameEvent::TerritoryChanged {
+                mine: mine.count(),
+                theirs: theirs.count(),
+            });
+        }
+
+        events
+    }
+}
+
+impl Default for EventSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Подписчик, который пишет события в лог как читаемую историю игры.
+pub fn log_subscriber(event: &GameEvent) {
+    logger::info(event.to_string(), "GameEvent");
+}
+
+/// Клетка в радиусе броска `thrower`, чей сплеш накрывает больше всего промокших
+/// на `SPLASH_DAMAGE` героев, и сами эти герои.
+fn bomb_landing(
+    prev: &GameContext,
+    cur: &GameContext,
+    thrower: &Position,
+) -> (Option<Position>, Vec<i32>) {
+    let soaked: Vec<(i32, Position)> = cur
+        .hero_store
+        .heroes
+        .iter()
+        .filter(|hero| {
+            prev.hero_store.get(hero.agent_id).is_some_and(|before| {
+                before.alive && hero.wetness - before.wetness >= SPLASH_DAMAGE
+            })
+        })
+        .map(|hero| (hero.agent_id, hero.position))
+        .collect();
+
+    let best = cur
+        .tilemap
+        .tiles
+        .iter()
+        .map(|tile| tile.position)
+        .filter(|position| position.distance(thrower) <= THROW_RANGE)
+        .map(|position| {
+            let hits: Vec<i32> = soaked
+                .iter()
+                .filter(|(_, hero)| hero.distance_8x(&position) <= 1)
+                .map(|(id, _)| *id)
+                .collect();
+            (position, hits)
+        })
+        .max_by_key(|(_, hits)| hits.len());
+
+    match best {
+        Some((position, hits)) if !hits.is_empty() => (Some(position), hits),
+        _ => (None, vec![]),
+    }
+}
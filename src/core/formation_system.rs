@@ -0,0 +1,172 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::{
+    core::predict_system::PredictSystem,
+    data::{bitboard::BitBoard, game_context::GameContext, hero::Hero, position::Position},
+    infra::{debug_overlay, logger, pathfinder, profiler::Profiler},
+};
+
+/// Минимальное расстояние (по 8 направлениям) между союзниками: одна бомба накрывает 3x3
+pub const MIN_SPREAD: i32 = 3;
+/// Насколько далеко (в шагах) ищем новую позицию за один план
+pub const PLAN_RADIUS: i32 = 4;
+/// Союзник ближе этого расстояния (манхэттен) считается поддержкой
+pub const SUPPORT_RANGE: i32 = 6;
+
+const TERRITORY_WEIGHT: i32 = 4;
+const SUPPORT_BONUS: i32 = 2;
+const COVER_BONUS: i32 = 6;
+const STEP_COST: i32 = 1;
+const CROWD_PENALTY: i32 = 50;
+
+/// Назначение героя в построении.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormationMove {
+    pub agent_id: i32,
+    pub target: Position,
+    /// Следующий шаг к цели; None — стоять (цель достигнута или путь занят союзником)
+    pub step: Option<Position>,
+    /// Оценка цели по слагаемым
+    pub features: Vec<(&'static str, i32)>,
+}
+
+/// Командное построение: разные цели для героев, разнос против бомб,
+/// шаги без столкновений между своими.
+pub struct FormationSystem;
+
+impl FormationSystem {
+    /// `covers` — клетки рядом с укрытиями, за них даётся бонус.
+    pub fn plan(ctx: &GameContext, covers: &BitBoard) -> Vec<FormationMove> {
+        let _prof = Profiler::record("FormationSystem::plan");
+        let (width, height) = (ctx.tilemap.get_width(), ctx.tilemap.get_height());
+
+        let heroes: Vec<&Hero> = ctx.hero_store.my_heroes().collect();
+        let enemies =
+            BitBoard::from_positions(width, height, ctx.hero_store.enemies().map(|x| &x.position));
+        // свои герои не стены: кто кому мешает, решается при разводке шагов
+        let passable = ctx.tilemap.passable() & !enemies;
+
+        // позиции союзников: сначала текущие, по мере назначения — цели
+        let mut placed: Vec<Position> = heroes.iter().map(|x| x.position).collect();
+        let mut features = vec![vec![]; heroes.len()];
+
+        for (i, hero) in heroes.iter().enumerate() {
+            let others: Vec<Position> = placed
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, x)| *x)
+                .collect();
+            let others_board = BitBoard::from_positions(width, height, &others);
+
+            let best = reachable(ctx, &passable, &hero.position, PLAN_RADIUS)
+                .into_iter()
+                .filter(|(tile, _)| !others.contains(tile))
+                .map(|(tile, steps)| {
+                    let mut mine = others_board;
+                    mine.set(&tile);
+                    let (territory, _) = PredictSystem::territory_from(mine, enemies);
+
+                    let support = others
+                        .iter()
+                        .filter(|x| x.distance(&tile) <= SUPPORT_RANGE)
+                        .count() as i32;
+                    let crowded = others
+                        .iter()
+                        .filter(|x| x.distance_8x(&tile) < MIN_SPREAD)
+                        .count() as i32;
+                    let cover = if covers.get(&tile) { COVER_BONUS } else { 0 };
+
+                    let score = vec![
+                        ("territory", territory.count() as i32 * TERRITORY_WEIGHT),
+                        ("support", support * SUPPORT_BONUS),
+                        ("cover", cover),
+                        ("crowding", -crowded * CROWD_PENALTY),
+                        ("steps", -steps * STEP_COST),
+                    ];
+                    (tile, score)
+                })
+                .max_by_key(|(_, score)| score.iter().map(|(_, x)| x).sum::<i32>());
+
+            if let Some((tile, score)) = best {
+                placed[i] = tile;
+                features[i] = score;
+            }
+        }
+
+        let mut moves: Vec<FormationMove> = heroes
+            .iter()
+            .zip(placed)
+            .zip(features)
+            .map(|((hero, target), features)| FormationMove {
+                agent_id: hero.agent_id,
+                target,
+                step: pathfinder::find_path_on(ctx, &passable, &hero.position, &target)
+                    .and_then(|path| path.get(1).copied()),
+                features,
+            })
+            .collect();
+
+        Self::resolve_conflicts(&heroes, &mut moves);
+        for (hero, planned) in heroes.iter().zip(&moves) {
+            debug_overlay::arrow("formation", &hero.position, &planned.target, "#FCA311");
+        }
+        logger::log(&moves, "FormationSystem::plan");
+        moves
+    }
+
+    /// Снимает шаги, при которых свои мешают друг другу: два героя в одну клетку,
+    /// обмен местами или шаг в клетку союзника, который сам остаётся на месте.
+    fn resolve_conflicts(heroes: &[&Hero], moves: &mut [FormationMove]) {
+        loop {
+            let mut changed = false;
+
+            for i in 0..moves.len() {
+                let Some(step) = moves[i].step else {
+                    continue;
+                };
+                let blocked = (0..moves.len()).filter(|j| *j != i).any(|j| {
+                    let ally_at = heroes[j].position;
+                    let ally_step = moves[j].step;
+                    // раньше в списке — выше приоритет
+                    (j < i && ally_step == Some(step))
+                        || (ally_at == step && ally_step.is_none())
+                        || (ally_at == step && ally_step == Some(heroes[i].position) && j < i)
+                });
+                if blocked {
+                    moves[i].step = None;
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                return;
+            }
+        }
+    }
+}
+
+/// Клетки, до которых можно дойти за `radius` шагов, с числом шагов (включая стартовую).
+fn reachable(
+    ctx: &GameContext,
+    passable: &BitBoard,
+    start: &Position,
+    radius: i32,
+) -> Vec<(Position, i32)> {
+    let mut visited = HashSet::from([*start]);
+    let mut queue = VecDeque::from([(*start, 0)]);
+    let mut out = vec![];
+
+    while let Some((position, steps)) = queue.pop_front() {
+        out.push((position, steps));
+        if steps == radius {
+            continue;
+        }
+        for next in ctx.tilemap.neighbors(&position) {
+            if passable.get(&next) && visited.insert(next) {
+                queue.push_back((next, steps + 1));
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,94 @@
+use std::error::Error;
+
+use crate::{
+    core::{agg_system::AggSystem, inference_system::InferenceSystem},
+    data::{game_context::GameContext, hero::HeroAction},
+    infra::{
+        debug_overlay,
+        input_reader::{read_for_loop, read_for_loop_update},
+        logger, profiler,
+        simulator::simulator_action,
+        snapshot::Recorder,
+        turn_clock::TurnClock,
+    },
+};
+
+/// Ход бота без окна: чтение входа referee, решение `AggSystem` и сверка
+/// с нашей симуляцией. Общий для консольного бота и визуализатора.
+pub struct GameLoop {
+    pub ctx: GameContext,
+    pub agg_system: AggSystem,
+    pub clock: TurnClock,
+    /// Снимки ходов в SOAK_SNAPSHOTS, если задан
+    recorder: Recorder,
+    /// Состояние после нашего симулятора, сверяется со следующим входом referee
+    expected: Option<GameContext>,
+    /// Итоги партии уже сохранены (визуализатор читает и после конца ввода)
+    finished: bool,
+}
+
+impl GameLoop {
+    pub fn new(ctx: GameContext) -> Self {
+        Self {
+            ctx,
+            agg_system: AggSystem::new(),
+            clock: TurnClock::default(),
+            recorder: Recorder::from_env(),
+            expected: None,
+            finished: false,
+        }
+    }
+
+    /// Читает ход; Err — ввод кончился или сломался (ошибка уже в логе).
+    pub fn read(&mut self) -> Result<(), Box<dyn Error>> {
+        let read = read_for_loop(&mut self.ctx, &mut self.clock);
+        if let Err(err) = &read {
+            if let Err(inner) = read_for_loop_update(&mut self.ctx) {
+                logger::error(
+                    format!("ACTION:{}", inner),
+                    "GameLoop::read_for_loop_update",
+                );
+            }
+            logger::error(format!("{:?}", err), "GameLoop::read");
+            // партия для сводки закончена
+            if !self.finished {
+                self.finished = true;
+                self.recorder.finish();
+                profiler::flush_game();
+            }
+        }
+        logger::set_turn(self.clock.turn());
+        debug_overlay::clear();
+
+        if let Some(simulated) = self.expected.take() {
+            let desyncs = InferenceSystem::check_simulation(&simulated, &self.ctx);
+            if !desyncs.is_empty() {
+                logger::log(&desyncs, "GameLoop::simulation_desync");
+            }
+        }
+        read
+    }
+
+    /// Команды на текущий ход; снимок хода уходит в `Recorder`.
+    pub fn decide(&mut self) -> Vec<HeroAction> {
+        let actions = self.agg_system.process(&self.ctx, &self.clock);
+        profiler::flush_turn(self.clock.turn());
+        self.recorder
+            .record(self.clock.turn(), &self.ctx, &debug_overlay::snapshot());
+        actions
+    }
+
+    /// Применяет наши команды к копии состояния, чтобы сверить её со следующим входом.
+    /// Ошибка симулятора только пишется в лог: ход уже отдан, бот продолжает игру.
+    pub fn simulate(&mut self, actions: Vec<HeroAction>) {
+        if actions.is_empty() {
+            return;
+        }
+        // живое состояние не трогаем: выбывших по симуляции referee должен подтвердить сам
+        let mut expected = self.ctx.clone();
+        match simulator_action(&mut expected, actions) {
+            Ok(_) => self.expected = Some(expected),
+            Err(err) => logger::error(err, "GameLoop::simulate"),
+        }
+    }
+}
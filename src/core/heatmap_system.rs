@@ -0,0 +1,92 @@
+use crate::{
+    core::{
+        opponent_model::OpponentModel, predict_system::PredictSystem, shooter_system::ShooterSystem,
+    },
+    data::{bitboard::BitBoard, game_context::GameContext, hero::Hero, position::Position},
+};
+
+/// Значение на клетку по индексу `TileMap::to_index`; None — клетка не участвует (стены, недостижимые).
+pub type ScalarField = Vec<Option<f32>>;
+
+/// Скалярные поля по карте для тепловых карт визуализатора.
+pub struct HeatmapSystem;
+
+impl HeatmapSystem {
+    /// 1.0 — наша территория, -1.0 — вражеская, 0.0 — ничья.
+    pub fn territory(ctx: &GameContext) -> ScalarField {
+        let (mine, theirs) = PredictSystem::territory(ctx);
+        Self::by_tile(ctx, |position| {
+            Some(if mine.get(position) {
+                1.0
+            } else if theirs.get(position) {
+                -1.0
+            } else {
+                0.0
+            })
+        })
+    }
+
+    /// Ожидаемый входящий урон по `hero`, если бы он стоял на клетке.
+    pub fn threat(ctx: &GameContext, opponents: &OpponentModel, hero: &Hero) -> ScalarField {
+        let passable = ctx.tilemap.passable();
+        Self::by_tile(ctx, |position| {
+            if !passable.get(position) {
+                return None;
+            }
+            let placed = Hero {
+                position: *position,
+                ..*hero
+            };
+            Some(opponents.expected_threat(ctx, &placed))
+        })
+    }
+
+    /// Худшая (наименьшая) защита укрытием от противников `hero` на клетке.
+    pub fn cover(ctx: &GameContext, hero: &Hero) -> ScalarField {
+        let passable = ctx.tilemap.passable();
+        let enemies: Vec<Position> = ctx
+            .hero_store
+            .opponents_of(hero)
+            .map(|x| x.position)
+            .collect();
+        Self::by_tile(ctx, |position| {
+            if !passable.get(position) {
+                return None;
+            }
+            enemies
+                .iter()
+                .map(|enemy| ShooterSystem::cover_protection(ctx, enemy, position))
+                .reduce(f32::min)
+                .or(Some(0.0))
+        })
+    }
+
+    /// Число шагов от `hero` по пустым клеткам (другие герои не мешают).
+    pub fn distance(ctx: &GameContext, hero: &Hero) -> ScalarField {
+        let (width, height) = (ctx.tilemap.get_width(), ctx.tilemap.get_height());
+        let passable = ctx.tilemap.passable();
+        let mut field: ScalarField = vec![None; ctx.tilemap.tiles.len()];
+
+        let mut reached = BitBoard::single(width, height, &hero.position);
+        let mut frontier = reached;
+        let mut steps = 0.0;
+        while !frontier.is_empty() {
+            for position in frontier.iter() {
+                field[ctx.tilemap.to_index(&position)] = Some(steps);
+            }
+            let next = reached | (reached.dilate4() & passable);
+            frontier = next & !reached;
+            reached = next;
+            steps += 1.0;
+        }
+        field
+    }
+
+    fn by_tile(ctx: &GameContext, value: impl Fn(&Position) -> Option<f32>) -> ScalarField {
+        let mut field: ScalarField = vec![None; ctx.tilemap.tiles.len()];
+        for tile in &ctx.tilemap.tiles {
+            field[ctx.tilemap.to_index(&tile.position)] = value(&tile.position);
+        }
+        field
+    }
+}
@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+
+use crate::{
+    core::shooter_system::ShooterSystem,
+    data::{
+        game_context::GameContext,
+        hero::{
+            splash_distance, Hero, HeroAction, HeroActionVariant, HUNKER_REDUCTION, SPLASH_DAMAGE,
+            THROW_RANGE,
+        },
+        position::Position,
+    },
+    infra::logger,
+};
+
+/// Действие врага, восстановленное по разнице двух ходов.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InferredAction {
+    Move {
+        agent_id: i32,
+        from: Position,
+        to: Position,
+    },
+    /// Цель None, если прирост мокрости не удалось объяснить
+    Shoot { agent_id: i32, target: Option<i32> },
+    /// Наши герои, попавшие под сплеш
+    Throw { agent_id: i32, hits: Vec<i32> },
+    /// Наши выстрелы намочили его на четверть меньше ожидаемого
+    HunkerDown { agent_id: i32 },
+    /// Ни перемещения, ни атаки, и спрятаться не подтверждено
+    Idle { agent_id: i32 },
+}
+
+/// Расхождение между ожидаемым и тем, что прислал referee.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Desync {
+    /// Героя нет во входе referee
+    Missing { agent_id: i32 },
+    /// Симулятор поставил героя не туда
+    Position {
+        agent_id: i32,
+        expected: Position,
+        actual: Position,
+    },
+    /// MOVE отдан, но герой остался на месте
+    MoveBlocked { agent_id: i32, target: Position },
+    /// SHOOT отдан, но кулдаун не сбросился
+    ShotRejected { agent_id: i32, target: i32 },
+    /// THROW отдан, но бомб не убавилось
+    ThrowRejected { agent_id: i32, target: Position },
+}
+
+pub struct InferenceSystem;
+
+impl InferenceSystem {
+    /// Восстанавливает наиболее вероятные действия врагов между `prev` и `cur`;
+    /// `ours` — наши команды того же хода, по их урону видно HUNKER_DOWN.
+    pub fn infer(
+        prev: &GameContext,
+        cur: &GameContext,
+        ours: &[HeroAction],
+    ) -> Vec<InferredAction> {
+        let mut actions = vec![];
+
+        // урон по нашим героям, который ещё предстоит объяснить
+        let mut unexplained: HashMap<i32, i32> = cur
+            .hero_store
+            .my_heroes()
+            .filter_map(|hero| {
+                let before = prev.hero_store.get(hero.agent_id)?;
+                Some((hero.agent_id, hero.wetness - before.wetness))
+            })
+            .filter(|(_, delta)| *delta > 0)
+            .collect();
+
+        let enemies: Vec<(&Hero, &Hero)> = cur
+            .hero_store
+            .enemies()
+            .filter_map(|enemy| Some((prev.hero_store.get(enemy.agent_id)?, enemy)))
+            .collect();
+
+        // сначала бомбы: их урон фиксирован, выстрелы объясняют остаток
+        for (before, enemy) in &enemies {
+            if enemy.splash_bombs >= before.splash_bombs {
+                continue;
+            }
+            let hits: Vec<i32> = unexplained
+                .iter()
+                .filter(|(_, left)| **left >= SPLASH_DAMAGE)
+                .filter_map(|(id, _)| cur.hero_store.get(*id))
+                .filter(|target| splash_distance(&enemy.position, &target.position) <= THROW_RANGE)
+                .map(|target| target.agent_id)
+                .collect();
+            for id in &hits {
+                if let Some(left) = unexplained.get_mut(id) {
+                    *left -= SPLASH_DAMAGE;
+                }
+            }
+            actions.push(InferredAction::Throw {
+                agent_id: enemy.agent_id,
+                hits,
+            });
+        }
+
+        for (before, enemy) in &enemies {
+            let moved = before.position != enemy.position;
+            let threw = enemy.splash_bombs < before.splash_bombs;
+            let shot = fired(enemy);
+            // боевое действие за ход одно
+            let hunker = !shot && !threw && hunkered(cur, before, enemy, ours);
+
+            if moved {
+                actions.push(InferredAction::Move {
+                    agent_id: enemy.agent_id,
+                    from: before.position,
+                    to: enemy.position,
+                });
+            }
+            if shot {
+                let target = attribute_shot(cur, enemy, &unexplained);
+                if let Some(id) = target {
+                    let damage = cur
+                        .hero_store
+                        .get(id)
+                        .map(|t| ShooterSystem::expected_damage(cur, enemy, t))
+                        .unwrap_or(0);
+                    if let Some(left) = unexplained.get_mut(&id) {
+                        *left -= damage;
+                    }
+                }
+                actions.push(InferredAction::Shoot {
+                    agent_id: enemy.agent_id,
+                    target,
+                });
+            }
+            if hunker {
+                actions.push(InferredAction::HunkerDown {
+                    agent_id: enemy.agent_id,
+                });
+            } else if !moved && !threw && !shot {
+                actions.push(InferredAction::Idle {
+                    agent_id: enemy.agent_id,
+                });
+            }
+        }
+
+        logger::log(&actions, "InferenceSystem::infer");
+
+        actions
+    }
+
+    /// Проверяет, что отданные нами команды действительно исполнились.
+    pub fn check_actions(
+        prev: &GameContext,
+        cur: &GameContext,
+        actions: &[HeroAction],
+    ) -> Vec<Desync> {
+        let mut desyncs = vec![];
+
+        for HeroAction(agent_id, variants) in actions {
+            // выбывание — исход хода, а не рассинхрон
+            if cur.hero_store.died_this_turn().contains(agent_id) {
+                continue;
+            }
+            let (Some(before), Some(after)) = (
+                prev.hero_store.get(*agent_id),
+                cur.hero_store.get(*agent_id).filter(|x| x.alive),
+            ) else {
+                desyncs.push(Desync::Missing {
+                    agent_id: *agent_id,
+                });
+                continue;
+            };
+
+            for variant in variants {
+                match variant {
+                    HeroActionVariant::Move(target) => {
+                        if before.position != *target && before.position == after.position {
+                            desyncs.push(Desync::MoveBlocked {
+                                agent_id: *agent_id,
+                                target: *target,
+                            });
+                        }
+                    }
+                    HeroActionVariant::Shoot { id } => {
+                        if !fired(after) {
+                            desyncs.push(Desync::ShotRejected {
+                                agent_id: *agent_id,
+                                target: *id,
+                            });
+                        }
+                    }
+                    HeroActionVariant::Throw(target) => {
+                        if after.splash_bombs >= before.splash_bombs {
+                            desyncs.push(Desync::ThrowRejected {
+                                agent_id: *agent_id,
+                                target: *target,
+                            });
+                        }
+                    }
+                    HeroActionVariant::HunkerDown | HeroActionVariant::Message { .. } => {}
+                }
+            }
+        }
+
+        desyncs
+    }
+
+    /// Сравнивает состояние после нашего симулятора с тем, что прислал referee.
+    pub fn check_simulation(expected: &GameContext, actual: &GameContext) -> Vec<Desync> {
+        expected
+            .hero_store
+            .my_heroes()
+            .filter_map(
+                |hero| match actual.hero_store.get(hero.agent_id).filter(|x| x.alive) {
+                    None => Some(Desync::Missing {
+                        agent_id: hero.agent_id,
+                    }),
+                    Some(real) if real.position != hero.position => Some(Desync::Position {
+                        agent_id: hero.agent_id,
+                        expected: hero.position,
+                        actual: real.position,
+                    }),
+                    Some(_) => None,
+                },
+            )
+            .collect()
+    }
+}
+
+/// Кулдаун сбросился на `shoot_cooldown` — герой выстрелил в этот ход.
+fn fired(hero: &Hero) -> bool {
+    hero.shoot_cooldown > 0 && hero.cooldown == hero.shoot_cooldown
+}
+
+/// Враг прятался, если прирост мокрости от наших выстрелов ближе к урезанному
+/// на `HUNKER_REDUCTION` урону, чем к полному.
+fn hunkered(cur: &GameContext, before: &Hero, enemy: &Hero, ours: &[HeroAction]) -> bool {
+    if !enemy.alive {
+        return false;
+    }
+    let (mut shots, mut splash) = (0, 0);
+    for HeroAction(agent_id, variants) in ours {
+        // выстрел засчитываем, только если он действительно был
+        let Some(shooter) = cur.hero_store.get(*agent_id).filter(|x| fired(x)) else {
+            continue;
+        };
+        for variant in variants {
+            match variant {
+                HeroActionVariant::Shoot { id } if *id == enemy.agent_id => {
+                    shots += ShooterSystem::expected_damage(cur, shooter, enemy);
+                }
+                HeroActionVariant::Throw(position)
+                    if position.distance_8x(&enemy.position) <= 1 =>
+                {
+                    splash += SPLASH_DAMAGE;
+                }
+                _ => {}
+            }
+        }
+    }
+    if shots == 0 {
+        return false;
+    }
+    let gain = enemy.wetness - before.wetness;
+    let reduced = splash + (shots as f32 * (1.0 - HUNKER_REDUCTION)).round() as i32;
+    (gain - reduced).abs() < (gain - splash - shots).abs()
+}
+
+/// Подбирает нашего героя, чей прирост мокрости лучше всего объясняется выстрелом `enemy`.
+fn attribute_shot(ctx: &GameContext, enemy: &Hero, unexplained: &HashMap<i32, i32>) -> Option<i32> {
+    unexplained
+        .iter()
+        .filter(|(_, left)| **left > 0)
+        .filter_map(|(id, left)| {
+            let target = ctx.hero_store.get(*id)?;
+            let expected = ShooterSystem::expected_damage(ctx, enemy, target);
+            if expected == 0 {
+                return None;
+            }
+            Some((*id, (left - expected).abs()))
+        })
+        .min_by_key(|(id, miss)| (*miss, *id))
+        .map(|(id, _)| id)
+}
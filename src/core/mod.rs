@@ -1,5 +1,14 @@
 pub mod agg_system;
 pub mod ai_system;
 pub mod cover_system;
+pub mod endgame_system;
+pub mod event_system;
+pub mod formation_system;
+pub mod game_loop;
+pub mod heatmap_system;
+pub mod inference_system;
+pub mod opponent_model;
 pub mod predict_system;
+pub mod rationale;
 pub mod shooter_system;
+pub mod state_machine;
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use crate::{
+    core::{inference_system::InferredAction, shooter_system::ShooterSystem},
+    data::{
+        game_context::GameContext,
+        hero::{splash_distance, Hero, SPLASH_DAMAGE, THROW_RANGE},
+        position::Position,
+    },
+};
+
+/// История наблюдений за одним вражеским агентом.
+#[derive(Debug, Clone, Default)]
+pub struct EnemyHistory {
+    pub agent_id: i32,
+    pub turns_observed: i32,
+    /// Позиции по ходам, начиная с первого наблюдения
+    pub positions: Vec<Position>,
+    /// Ходы, в которые агент сместился
+    pub moves: i32,
+    /// Цели выстрелов (None, если цель определить не удалось)
+    pub shots: Vec<Option<i32>>,
+    pub bombs_used: i32,
+    /// Ходы, в которые агент прятался (видно по урону от наших выстрелов)
+    pub hunkers: i32,
+    /// Ходы без перемещения, атаки и подтверждённого укрытия
+    pub idles: i32,
+}
+
+impl EnemyHistory {
+    pub fn new(agent_id: i32) -> Self {
+        Self {
+            agent_id,
+            ..Default::default()
+        }
+    }
+}
+
+/// Оценка вероятностей следующего действия врага.
+///
+/// `moves` — вероятность перемещения, остальные поля — распределение
+/// боевого действия и в сумме дают 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ActionDistribution {
+    pub moves: f32,
+    pub shoot: f32,
+    pub throw: f32,
+    pub hunker: f32,
+    pub idle: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OpponentModel {
+    history: HashMap<i32, EnemyHistory>,
+}
+
+impl OpponentModel {
+    pub fn new() -> Self {
+        Self {
+            history: HashMap::new(),
+        }
+    }
+
+    pub fn history(&self, agent_id: i32) -> Option<&EnemyHistory> {
+        self.history.get(&agent_id)
+    }
+
+    /// Дописывает историю врагов действиями, выведенными из разницы с предыдущим ходом.
+    pub fn update(
+        &mut self,
+        prev: Option<&GameContext>,
+        ctx: &GameContext,
+        inferred: &[InferredAction],
+    ) {
+        for enemy in ctx.hero_store.enemies() {
+            let entry = self
+                .history
+                .entry(enemy.agent_id)
+                .or_insert_with(|| EnemyHistory::new(enemy.agent_id));
+            if prev.is_some() {
+                entry.turns_observed += 1;
+            }
+            entry.positions.push(enemy.position);
+        }
+
+        let Some(prev) = prev else {
+            return;
+        };
+
+        for action in inferred {
+            match *action {
+                InferredAction::Move { agent_id, .. } => {
+                    if let Some(entry) = self.history.get_mut(&agent_id) {
+                        entry.moves += 1;
+                    }
+                }
+                InferredAction::Shoot { agent_id, target } => {
+                    if let Some(entry) = self.history.get_mut(&agent_id) {
+                        entry.shots.push(target);
+                    }
+                }
+                InferredAction::Throw { agent_id, .. } => {
+                    let used = prev
+                        .hero_store
+                        .get(agent_id)
+                        .zip(ctx.hero_store.get(agent_id))
+                        .map(|(before, after)| before.splash_bombs - after.splash_bombs)
+                        .unwrap_or(1);
+                    if let Some(entry) = self.history.get_mut(&agent_id) {
+                        entry.bombs_used += used;
+                    }
+                }
+                InferredAction::HunkerDown { agent_id } => {
+                    if let Some(entry) = self.history.get_mut(&agent_id) {
+                        entry.hunkers += 1;
+                    }
+                }
+                InferredAction::Idle { agent_id } => {
+                    if let Some(entry) = self.history.get_mut(&agent_id) {
+                        entry.idles += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Вероятности следующего действия с учётом истории и текущих ограничений.
+    pub fn predict(&self, enemy: &Hero) -> ActionDistribution {
+        let history = self.history.get(&enemy.agent_id);
+        let turns = history.map(|h| h.turns_observed).unwrap_or(0) as f32;
+
+        // сглаживание Лапласа: без истории все варианты равновероятны
+        let smooth = |count: i32| (count as f32 + 1.0) / (turns + 2.0);
+
+        let moves = smooth(history.map(|h| h.moves).unwrap_or(0));
+
+        let mut shoot = if enemy.cooldown > 0 {
+            0.0
+        } else {
+            smooth(history.map(|h| h.shots.len() as i32).unwrap_or(0))
+        };
+        let mut throw = if enemy.splash_bombs <= 0 {
+            0.0
+        } else {
+            smooth(history.map(|h| h.bombs_used).unwrap_or(0))
+        };
+        let mut hunker = smooth(history.map(|h| h.hunkers).unwrap_or(0));
+
+        let total = shoot + throw + hunker;
+        if total > 1.0 {
+            shoot /= total;
+            throw /= total;
+            hunker /= total;
+        }
+
+        ActionDistribution {
+            moves,
+            shoot,
+            throw,
+            hunker,
+            idle: (1.0 - shoot - throw - hunker).max(0.0),
+        }
+    }
+
+    /// Ожидаемый входящий урон по герою на следующем ходу.
+    pub fn expected_threat(&self, ctx: &GameContext, hero: &Hero) -> f32 {
+        ctx.hero_store
+            .opponents_of(hero)
+            .map(|enemy| {
+                let dist = self.predict(enemy);
+                let shot = ShooterSystem::expected_damage(ctx, enemy, hero) as f32;
+                // враг может подойти на клетку перед броском
+                let splash = if splash_distance(&enemy.position, &hero.position) <= THROW_RANGE + 1
+                {
+                    SPLASH_DAMAGE as f32
+                } else {
+                    0.0
+                };
+                dist.shoot * shot + dist.throw * splash
+            })
+            .sum()
+    }
+}
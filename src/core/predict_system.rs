@@ -1,61 +1,97 @@
-use std::collections::{HashSet, VecDeque};
-
 use crate::{
-    data::{game_context::GameContext, position::Position},
-    infra::logger,
+    data::{bitboard::BitBoard, game_context::GameContext},
+    infra::{logger, profiler::Profiler},
 };
 
+/// Ходов в игре
+pub const MAX_TURNS: u32 = 100;
+
+/// Счёт партии: referee его не присылает, поэтому копим сами по ходам.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Score {
+    pub mine: i32,
+    pub theirs: i32,
+}
+
+impl Score {
+    /// Начисляет очки за ход: у кого больше клеток, тот получает разницу.
+    pub fn add_turn(&mut self, ctx: &GameContext) {
+        let (mine, theirs) = PredictSystem::predict(ctx);
+        if mine > theirs {
+            self.mine += mine - theirs;
+        } else {
+            self.theirs += theirs - mine;
+        }
+    }
+
+    pub fn lead(&self) -> i32 {
+        self.mine - self.theirs
+    }
+}
+
 pub struct PredictSystem {}
 
 impl PredictSystem {
     pub fn process(ctx: &GameContext) {}
 
     pub fn predict(ctx: &GameContext) -> (i32, i32) {
-        let my_heroes: Vec<_> = ctx
-            .hero_store
-            .heroes
-            .iter()
-            .filter(|x| x.is_owner)
-            .collect();
-        let enemies: Vec<_> = ctx
-            .hero_store
-            .heroes
-            .iter()
-            .filter(|x| !x.is_owner)
-            .collect();
-
-        let mut enemy_score = 0;
-        let mut my_score = 0;
-
-        for tile in &ctx.tilemap.tiles {
-            // if visited.contains(&tile.position) {
-            //     continue;
-            // }
-            // visited.insert(tile.position);
-
-            let my_min = my_heroes
-                .iter()
-                .map(|h| h.position.distance(&tile.position))
-                .min()
-                .unwrap_or(i32::MAX);
-
-            let enemy_min = enemies
-                .iter()
-                .map(|h| h.position.distance(&tile.position))
-                .min()
-                .unwrap_or(i32::MAX);
-
-            if my_min == enemy_min {
-                continue;
-            } else if my_min < enemy_min {
-                my_score += 1;
-            } else {
-                enemy_score += 1;
-            }
-        }
+        let _prof = Profiler::record("PredictSystem::predict");
+        let (my_board, enemy_board) = Self::territory(ctx);
+        let my_score = my_board.count() as i32;
+        let enemy_score = enemy_board.count() as i32;
 
         logger::log(&(my_score, enemy_score), "PredictSystem:predict");
 
         return (my_score, enemy_score);
     }
+
+    /// Очки, которые добавит текущий перевес по территории за оставшиеся ходы
+    /// (уже набранный счёт во входе не приходит).
+    pub fn projected_lead(ctx: &GameContext, turns_left: u32) -> i32 {
+        let (my_score, enemy_score) = Self::predict(ctx);
+        (my_score - enemy_score) * turns_left as i32
+    }
+
+    /// Перевес по очкам к концу игры, если территория не изменится.
+    pub fn projected_final_lead(ctx: &GameContext, score: &Score, turns_left: u32) -> i32 {
+        score.lead() + Self::projected_lead(ctx, turns_left)
+    }
+
+    /// Клетки, ближе (по манхэттену) к нашим героям и к вражеским; ничьи не входят никуда.
+    pub fn territory(ctx: &GameContext) -> (BitBoard, BitBoard) {
+        let (width, height) = (ctx.tilemap.get_width(), ctx.tilemap.get_height());
+
+        Self::territory_from(
+            BitBoard::from_positions(
+                width,
+                height,
+                ctx.hero_store.my_heroes().map(|x| &x.position),
+            ),
+            BitBoard::from_positions(width, height, ctx.hero_store.enemies().map(|x| &x.position)),
+        )
+    }
+
+    /// То же, что `territory`, для произвольной расстановки героев.
+    pub fn territory_from(my_heroes: BitBoard, enemies: BitBoard) -> (BitBoard, BitBoard) {
+        let mut my_reach = my_heroes;
+        let mut enemy_reach = enemies;
+
+        let mut mine = my_reach & !enemy_reach;
+        let mut theirs = enemy_reach & !my_reach;
+
+        // волна за волной: клетка принадлежит тому, кто дошёл первым
+        loop {
+            let my_next = my_reach.dilate4();
+            let enemy_next = enemy_reach.dilate4();
+            if my_next == my_reach && enemy_next == enemy_reach {
+                break;
+            }
+            mine |= my_next & !my_reach & !enemy_next;
+            theirs |= enemy_next & !enemy_reach & !my_next;
+            my_reach = my_next;
+            enemy_reach = enemy_next;
+        }
+
+        (mine, theirs)
+    }
 }
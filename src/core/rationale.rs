@@ -0,0 +1,30 @@
+use crate::{core::state_machine::State, data::hero::HeroActionVariant};
+
+/// Вариант, который система рассматривала для героя, с оценкой по признакам.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub actions: Vec<HeroActionVariant>,
+    /// (признак, вклад в оценку)
+    pub features: Vec<(&'static str, f32)>,
+    pub chosen: bool,
+}
+
+impl Candidate {
+    pub fn score(&self) -> f32 {
+        self.features
+            .iter()
+            .fold(0.0, |sum, (_, value)| sum + value)
+    }
+}
+
+/// Почему герой получил свою команду: режим, из чего он выбран,
+/// рассмотренные варианты и система, которая предложила итоговое действие.
+#[derive(Debug, Clone)]
+pub struct Rationale {
+    pub agent_id: i32,
+    pub state: State,
+    pub system: &'static str,
+    /// Входы выбора режима
+    pub inputs: Vec<(&'static str, f32)>,
+    pub candidates: Vec<Candidate>,
+}
@@ -1,6 +1,6 @@
 use crate::{
-    data::{game_context::GameContext, hero::Hero},
-    infra::logger,
+    data::{game_context::GameContext, hero::Hero, position::Position, tile::TileType},
+    infra::{logger, simulator},
 };
 
 pub struct ShooterSystem;
@@ -9,12 +9,36 @@ impl ShooterSystem {
     pub fn find_enemy<'a>(ctx: &'a GameContext, hero: &'a Hero) -> Option<&'a Hero> {
         logger::log("", "ShooterSystem:find_enemy");
         ctx.hero_store
-            .heroes
-            .iter()
-            .filter(|e| {
-                e.player != hero.player
-                    && e.position.distance_8x(&hero.position) < hero.optimal_range
-            }) // только враги
+            .opponents_of(hero)
+            .filter(|e| e.position.distance_8x(&hero.position) < hero.optimal_range) // только враги
             .min_by_key(|e| e.position.distance(&hero.position)) // ближайший
     }
+
+    /// Защита цели от выстрела из `from`: 0.0 без укрытия, иначе доля поглощённого урона.
+    pub fn cover_protection(ctx: &GameContext, from: &Position, target: &Position) -> f32 {
+        simulator::cover_protection(|x, y| tile_at(ctx, x, y), from, target)
+    }
+
+    /// Ожидаемый урон выстрела `shooter` по `target` с текущих позиций.
+    pub fn expected_damage(ctx: &GameContext, shooter: &Hero, target: &Hero) -> i32 {
+        simulator::shot_damage(
+            |x, y| tile_at(ctx, x, y),
+            shooter.soaking_power,
+            shooter.optimal_range,
+            &shooter.position,
+            &target.position,
+        )
+    }
+}
+
+fn tile_at(ctx: &GameContext, x: i32, y: i32) -> Option<TileType> {
+    if ctx.tilemap.out_of_bounds(x, y) {
+        return None;
+    }
+    ctx.tilemap
+        .get_tile(&Position {
+            x: x as usize,
+            y: y as usize,
+        })
+        .map(|tile| tile.tile_type)
 }
@@ -1,5 +1,50 @@
-pub enum State {}
+use std::collections::HashMap;
 
-pub enum Transition {}
+use crate::core::endgame_system::EndgameMode;
 
-pub struct StateMachine {}
+/// Режим героя на ходу: от него зависит, какие действия ему предлагаются.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    /// Угроза высокая: стреляем с места
+    Hold,
+    /// Угроза низкая: стреляем и занимаем позицию построения
+    Advance,
+    /// Проигрываем по территории: идём на позицию даже под огнём
+    Contest,
+    /// Перебор эндшпиля
+    Endgame(EndgameMode),
+    /// Не успели подумать: запасное действие
+    Fallback,
+}
+
+/// Смена режима героя между ходами.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transition {
+    pub agent_id: i32,
+    pub from: State,
+    pub to: State,
+}
+
+/// Текущий режим каждого нашего героя.
+#[derive(Debug, Default)]
+pub struct StateMachine {
+    states: HashMap<i32, State>,
+}
+
+impl StateMachine {
+    pub fn new() -> Self {
+        Self {
+            states: HashMap::new(),
+        }
+    }
+
+    /// Запоминает режим; Some, если он сменился.
+    pub fn set(&mut self, agent_id: i32, state: State) -> Option<Transition> {
+        let from = self.states.insert(agent_id, state)?;
+        (from != state).then_some(Transition {
+            agent_id,
+            from,
+            to: state,
+        })
+    }
+}
@@ -0,0 +1,272 @@
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, Not};
+
+use crate::data::position::Position;
+
+/// Слов в доске: 256 клеток покрывают карты до 20x12
+pub const WORDS: usize = 4;
+pub const MAX_CELLS: usize = WORDS * 64;
+
+/// Набор клеток карты в виде битов, индекс клетки — `y * width + x`.
+///
+/// Клетки с индексом от `MAX_CELLS` не хранятся: `set` их пропускает, `get` отдаёт false.
+/// Поместится ли карта целиком — `BitBoard::fits`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct BitBoard {
+    words: [u64; WORDS],
+    width: u8,
+    height: u8,
+}
+
+impl BitBoard {
+    pub fn fits(width: usize, height: usize) -> bool {
+        width * height <= MAX_CELLS && width <= u8::MAX as usize && height <= u8::MAX as usize
+    }
+
+    pub fn empty(width: usize, height: usize) -> Self {
+        Self {
+            words: [0; WORDS],
+            width: width as u8,
+            height: height as u8,
+        }
+    }
+
+    /// Все клетки карты.
+    pub fn full(width: usize, height: usize) -> Self {
+        let mut board = Self::empty(width, height);
+        let cells = width * height;
+        for (i, word) in board.words.iter_mut().enumerate() {
+            let from = i * 64;
+            if cells >= from + 64 {
+                *word = u64::MAX;
+            } else if cells > from {
+                *word = (1u64 << (cells - from)) - 1;
+            }
+        }
+        board
+    }
+
+    pub fn single(width: usize, height: usize, position: &Position) -> Self {
+        let mut board = Self::empty(width, height);
+        board.set(position);
+        board
+    }
+
+    pub fn from_positions<'a>(
+        width: usize,
+        height: usize,
+        positions: impl IntoIterator<Item = &'a Position>,
+    ) -> Self {
+        let mut board = Self::empty(width, height);
+        for position in positions {
+            board.set(position);
+        }
+        board
+    }
+
+    /// Столбец `x` целиком.
+    pub fn column(width: usize, height: usize, x: usize) -> Self {
+        let mut board = Self::empty(width, height);
+        for y in 0..height {
+            board.set_index(y * width + x);
+        }
+        board
+    }
+
+    pub fn width(&self) -> usize {
+        self.width as usize
+    }
+
+    pub fn height(&self) -> usize {
+        self.height as usize
+    }
+
+    #[inline]
+    fn index(&self, position: &Position) -> usize {
+        position.y * self.width as usize + position.x
+    }
+
+    #[inline]
+    fn set_index(&mut self, index: usize) {
+        if let Some(word) = self.words.get_mut(index / 64) {
+            *word |= 1 << (index % 64);
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, position: &Position) -> bool {
+        let index = self.index(position);
+        self.words
+            .get(index / 64)
+            .is_some_and(|word| word & (1 << (index % 64)) != 0)
+    }
+
+    #[inline]
+    pub fn set(&mut self, position: &Position) {
+        let index = self.index(position);
+        self.set_index(index);
+    }
+
+    #[inline]
+    pub fn clear(&mut self, position: &Position) {
+        let index = self.index(position);
+        if let Some(word) = self.words.get_mut(index / 64) {
+            *word &= !(1 << (index % 64));
+        }
+    }
+
+    pub fn count(&self) -> u32 {
+        self.words.iter().map(|x| x.count_ones()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|x| *x == 0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Position> + '_ {
+        let width = self.width as usize;
+        self.words.iter().enumerate().flat_map(move |(i, word)| {
+            let mut bits = *word;
+            std::iter::from_fn(move || {
+                if bits == 0 {
+                    return None;
+                }
+                let bit = bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                let index = i * 64 + bit;
+                Some(Position {
+                    x: index % width,
+                    y: index / width,
+                })
+            })
+        })
+    }
+
+    fn shl(&self, n: usize) -> Self {
+        let mut out = Self::empty(self.width(), self.height());
+        let (words, bits) = (n / 64, n % 64);
+        for i in (words..WORDS).rev() {
+            let mut value = self.words[i - words] << bits;
+            if bits > 0 && i > words {
+                value |= self.words[i - words - 1] >> (64 - bits);
+            }
+            out.words[i] = value;
+        }
+        out
+    }
+
+    fn shr(&self, n: usize) -> Self {
+        let mut out = Self::empty(self.width(), self.height());
+        let (words, bits) = (n / 64, n % 64);
+        for i in 0..WORDS.saturating_sub(words) {
+            let mut value = self.words[i + words] >> bits;
+            if bits > 0 && i + words + 1 < WORDS {
+                value |= self.words[i + words + 1] << (64 - bits);
+            }
+            out.words[i] = value;
+        }
+        out
+    }
+
+    /// Сдвиг всех клеток на (dx, dy) с отсечением ушедших за край (|dx|, |dy| <= 1).
+    pub fn shift(&self, dx: i32, dy: i32) -> Self {
+        let (width, height) = (self.width(), self.height());
+        // на пустой карте сдвигать нечего, а `width - 1` ниже ушёл бы в переполнение
+        if width == 0 || height == 0 {
+            return *self;
+        }
+        let mut board = *self;
+        if dx > 0 {
+            board = board.shl(1) & !Self::column(width, height, 0);
+        } else if dx < 0 {
+            board = board.shr(1) & !Self::column(width, height, width - 1);
+        }
+        if dy > 0 {
+            board = board.shl(width);
+        } else if dy < 0 {
+            board = board.shr(width);
+        }
+        board & Self::full(width, height)
+    }
+
+    /// Клетки плюс 4 соседа (`Position::DIRECTIONS`).
+    pub fn dilate4(&self) -> Self {
+        Position::DIRECTIONS
+            .iter()
+            .fold(*self, |acc, (dx, dy)| acc | self.shift(*dx, *dy))
+    }
+
+    /// Клетки плюс 8 соседей (`Position::WAYPOINTS`), например область сплеша.
+    pub fn dilate8(&self) -> Self {
+        let horizontal = *self | self.shift(1, 0) | self.shift(-1, 0);
+        horizontal | horizontal.shift(0, 1) | horizontal.shift(0, -1)
+    }
+
+    /// Всё, что достижимо из `self` шагами по 4 направлениям через `passable`.
+    pub fn flood_fill(&self, passable: &BitBoard) -> Self {
+        let mut reached = *self;
+        loop {
+            let next = reached | (reached.dilate4() & *passable);
+            if next == reached {
+                return reached;
+            }
+            reached = next;
+        }
+    }
+}
+
+impl BitAnd for BitBoard {
+    type Output = BitBoard;
+
+    fn bitand(mut self, rhs: Self) -> Self::Output {
+        self &= rhs;
+        self
+    }
+}
+
+impl BitAndAssign for BitBoard {
+    fn bitand_assign(&mut self, rhs: Self) {
+        for (lhs, rhs) in self.words.iter_mut().zip(rhs.words) {
+            *lhs &= rhs;
+        }
+    }
+}
+
+impl BitOr for BitBoard {
+    type Output = BitBoard;
+
+    fn bitor(mut self, rhs: Self) -> Self::Output {
+        self |= rhs;
+        self
+    }
+}
+
+impl BitOrAssign for BitBoard {
+    fn bitor_assign(&mut self, rhs: Self) {
+        for (lhs, rhs) in self.words.iter_mut().zip(rhs.words) {
+            *lhs |= rhs;
+        }
+    }
+}
+
+impl BitXor for BitBoard {
+    type Output = BitBoard;
+
+    fn bitxor(mut self, rhs: Self) -> Self::Output {
+        for (lhs, rhs) in self.words.iter_mut().zip(rhs.words) {
+            *lhs ^= rhs;
+        }
+        self
+    }
+}
+
+/// Дополнение в пределах карты.
+impl Not for BitBoard {
+    type Output = BitBoard;
+
+    fn not(mut self) -> Self::Output {
+        for word in self.words.iter_mut() {
+            *word = !*word;
+        }
+        self & Self::full(self.width(), self.height())
+    }
+}
@@ -1,10 +1,15 @@
-use crate::data::{hero::HeroStore, tilemap::TileMap};
+use crate::{
+    data::{hero::HeroStore, tile::Occupant, tilemap::TileMap},
+    infra::zobrist,
+};
 
 #[derive(Debug, Clone)]
 pub struct GameContext {
     pub player_id: i32,
     pub tilemap: TileMap,
     pub hero_store: HeroStore,
+    /// Zobrist-хеш состояния; симулятор обновляет его инкрементально
+    pub hash: u64,
 }
 
 impl GameContext {
@@ -13,6 +18,29 @@ impl GameContext {
             player_id: 0,
             tilemap: TileMap::new(0, 0),
             hero_store: HeroStore::new(),
+            hash: 0,
         }
     }
+
+    /// Полный пересчёт хеша, например после чтения хода от referee.
+    pub fn rehash(&mut self) {
+        self.hash = zobrist::hash_context(self);
+    }
+
+    /// Заново расставляет героев по клеткам и пересчитывает хеш после ручных правок.
+    pub fn sync(&mut self) {
+        for tile in self.tilemap.tiles.iter_mut() {
+            tile.occupant = Occupant::Nil;
+        }
+        for hero in self.hero_store.alive() {
+            if let Some(tile) = self.tilemap.get_tile_mut(&hero.position) {
+                tile.occupant = if hero.player == self.player_id {
+                    Occupant::Owner(hero.agent_id as usize)
+                } else {
+                    Occupant::Enemy(hero.agent_id as usize)
+                };
+            }
+        }
+        self.rehash();
+    }
 }
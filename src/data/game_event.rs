@@ -0,0 +1,83 @@
+use std::fmt::Display;
+
+use crate::data::position::Position;
+
+/// Что произошло за ход; строится сравнением двух состояний.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameEvent {
+    HeroMoved {
+        agent_id: i32,
+        from: Position,
+        to: Position,
+    },
+    /// Цель None, если выстрел не удалось привязать к герою
+    HeroShot {
+        agent_id: i32,
+        target: Option<i32>,
+    },
+    /// Место падения None, если ни одна клетка не объясняет попадания
+    BombLanded {
+        agent_id: i32,
+        position: Option<Position>,
+        hits: Vec<i32>,
+    },
+    HeroEliminated {
+        agent_id: i32,
+        position: Position,
+    },
+    /// Новый размер территории (наша, вражеская)
+    TerritoryChanged {
+        mine: u32,
+        theirs: u32,
+    },
+    /// Кулдаун выстрела дошёл до нуля
+    CooldownReady {
+        agent_id: i32,
+    },
+}
+
+impl GameEvent {
+    /// Герой, к которому относится событие.
+    pub fn agent_id(&self) -> Option<i32> {
+        match self {
+            GameEvent::HeroMoved { agent_id, .. }
+            | GameEvent::HeroShot { agent_id, .. }
+            | GameEvent::BombLanded { agent_id, .. }
+            | GameEvent::HeroEliminated { agent_id, .. }
+            | GameEvent::CooldownReady { agent_id } => Some(*agent_id),
+            GameEvent::TerritoryChanged { .. } => None,
+        }
+    }
+}
+
+impl Display for GameEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameEvent::HeroMoved { agent_id, from, to } => {
+                write!(f, "#{} moved ({}) -> ({})", agent_id, from, to)
+            }
+            GameEvent::HeroShot { agent_id, target } => match target {
+                Some(target) => write!(f, "#{} shot #{}", agent_id, target),
+                None => write!(f, "#{} shot", agent_id),
+            },
+            GameEvent::BombLanded {
+                agent_id,
+                position,
+                hits,
+            } => {
+                match position {
+                    Some(position) => write!(f, "#{} bomb at ({})", agent_id, position)?,
+                    None => write!(f, "#{} bomb", agent_id)?,
+                }
+                write!(f, " hits {:?}", hits)
+            }
+            GameEvent::HeroEliminated { agent_id, position } => {
+                write!(f, "#{} eliminated at ({})", agent_id, position)
+            }
+            GameEvent::TerritoryChanged { mine, theirs } => {
+                write!(f, "territory {} vs {}", mine, theirs)
+            }
+            GameEvent::CooldownReady { agent_id } => write!(f, "#{} ready to shoot", agent_id),
+        }
+    }
+}
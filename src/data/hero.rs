@@ -1,4 +1,24 @@
+use std::collections::HashMap;
+
 use crate::data::position::Position;
+
+/// Урон от сплеш-бомбы по каждой клетке 3x3
+pub const SPLASH_DAMAGE: i32 = 30;
+/// Максимальная дальность броска бомбы (манхэттен)
+pub const THROW_RANGE: i32 = 4;
+
+/// Манхэттен от `from` до ближайшей точки броска, сплеш 3x3 которой накрывает `target`;
+/// бросок достаёт цель, если это не больше `THROW_RANGE`.
+pub fn splash_distance(from: &Position, target: &Position) -> i32 {
+    let dx = (from.x as i32 - target.x as i32).abs();
+    let dy = (from.y as i32 - target.y as i32).abs();
+    (dx - 1).max(0) + (dy - 1).max(0)
+}
+/// Доля урона, которую снимает HUNKER_DOWN
+pub const HUNKER_REDUCTION: f32 = 0.25;
+/// Мокрость, при которой герой выбывает
+pub const MAX_WETNESS: i32 = 100;
+
 #[derive(Debug, Clone, Copy)]
 pub struct Hero {
     pub is_owner: bool,
@@ -12,25 +32,159 @@ pub struct Hero {
     pub cooldown: i32,
     pub wetness: i32,
     pub alive: bool,
+    /// Класс героя, определяется один раз по стартовым характеристикам
+    pub role: HeroRole,
 }
 
 impl Hero {}
 
+/// Класс героя. Характеристики класса: (shoot_cooldown, optimal_range, soaking_power, splash_bombs).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum HeroRole {
+    /// 1, 4, 16, 1 — стреляет каждый ход
+    Gunner,
+    /// 5, 6, 24, 0 — держит дальние линии из-за укрытий
+    Sniper,
+    /// 2, 2, 8, 3 — сближается и бросает бомбы
+    Bomber,
+    /// 2, 4, 16, 2
+    Assault,
+    /// 5, 2, 32, 1 — редкий, но сильный выстрел вблизи
+    Berserker,
+    /// Ещё не классифицирован
+    #[default]
+    Unknown,
+}
+
+impl HeroRole {
+    const PROFILES: [(HeroRole, [i32; 4]); 5] = [
+        (HeroRole::Gunner, [1, 4, 16, 1]),
+        (HeroRole::Sniper, [5, 6, 24, 0]),
+        (HeroRole::Bomber, [2, 2, 8, 3]),
+        (HeroRole::Assault, [2, 4, 16, 2]),
+        (HeroRole::Berserker, [5, 2, 32, 1]),
+    ];
+
+    /// Ближайший профиль по характеристикам; точное совпадение для известных классов.
+    pub fn classify(
+        shoot_cooldown: i32,
+        optimal_range: i32,
+        soaking_power: i32,
+        splash_bombs: i32,
+    ) -> HeroRole {
+        let stats = [shoot_cooldown, optimal_range, soaking_power, splash_bombs];
+        // урон между классами отличается шагом 8, остальное — на 1-4
+        let weights = [4, 4, 1, 4];
+
+        Self::PROFILES
+            .iter()
+            .min_by_key(|(_, profile)| {
+                (0..4)
+                    .map(|i| (stats[i] - profile[i]).abs() * weights[i])
+                    .sum::<i32>()
+            })
+            .map(|(role, _)| *role)
+            .unwrap_or_default()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct HeroStore {
+    /// Все герои игры, включая выбывших; добавлять через `push`
     pub heroes: Vec<Hero>,
-    pub my_heroes: Vec<Hero>,
-    pub enemies: Vec<Hero>,
+    /// agent_id -> индекс в `heroes`
+    index: HashMap<i32, usize>,
+    /// Герои, пропавшие из входа в последнем прочитанном ходу
+    died_this_turn: Vec<i32>,
 }
 
 impl HeroStore {
     pub fn new() -> HeroStore {
         Self {
             heroes: vec![],
-            my_heroes: vec![],
-            enemies: vec![],
+            index: HashMap::new(),
+            died_this_turn: vec![],
+        }
+    }
+
+    pub fn push(&mut self, hero: Hero) {
+        self.index.insert(hero.agent_id, self.heroes.len());
+        self.heroes.push(hero);
+    }
+
+    pub fn remove(&mut self, agent_id: i32) -> Option<Hero> {
+        let hero = self.heroes.remove(self.position_of(agent_id)?);
+        self.index = self
+            .heroes
+            .iter()
+            .enumerate()
+            .map(|(i, x)| (x.agent_id, i))
+            .collect();
+        Some(hero)
+    }
+
+    fn position_of(&self, agent_id: i32) -> Option<usize> {
+        match self.index.get(&agent_id) {
+            Some(&i) if self.heroes.get(i).is_some_and(|x| x.agent_id == agent_id) => Some(i),
+            // индекс устарел (heroes меняли напрямую) — ищем перебором
+            _ => self.heroes.iter().position(|x| x.agent_id == agent_id),
         }
     }
+
+    pub fn get(&self, agent_id: i32) -> Option<&Hero> {
+        self.position_of(agent_id).map(|i| &self.heroes[i])
+    }
+
+    pub fn get_mut(&mut self, agent_id: i32) -> Option<&mut Hero> {
+        self.position_of(agent_id).map(|i| &mut self.heroes[i])
+    }
+
+    /// Живые герои обеих сторон.
+    pub fn alive(&self) -> impl Iterator<Item = &Hero> {
+        self.heroes.iter().filter(|x| x.alive)
+    }
+
+    /// Наши живые герои.
+    pub fn my_heroes(&self) -> impl Iterator<Item = &Hero> {
+        self.alive().filter(|x| x.is_owner)
+    }
+
+    /// Живые герои противника.
+    pub fn enemies(&self) -> impl Iterator<Item = &Hero> {
+        self.alive().filter(|x| !x.is_owner)
+    }
+
+    /// Живые противники конкретного героя (с любой стороны).
+    pub fn opponents_of<'a>(&'a self, hero: &'a Hero) -> impl Iterator<Item = &'a Hero> {
+        self.alive().filter(move |x| x.player != hero.player)
+    }
+
+    /// Живые герои класса `role` (обе стороны).
+    pub fn with_role(&self, role: HeroRole) -> impl Iterator<Item = &Hero> {
+        self.alive().filter(move |x| x.role == role)
+    }
+
+    /// Отмечает живыми героев из входа хода, остальных — выбывшими.
+    pub fn update_alive(&mut self, present: &[i32]) {
+        self.died_this_turn.clear();
+        for hero in self.heroes.iter_mut() {
+            let alive = present.contains(&hero.agent_id);
+            if hero.alive && !alive {
+                self.died_this_turn.push(hero.agent_id);
+            }
+            hero.alive = alive;
+        }
+    }
+
+    pub fn died_this_turn(&self) -> &[i32] {
+        &self.died_this_turn
+    }
+}
+
+impl Default for HeroStore {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 /// Возможные действия героя за один ход.
@@ -49,6 +203,7 @@ pub enum HeroActionVariant {
 }
 
 /// Действие героя в виде пары (id агента, список действий)
+#[derive(Debug, Clone)]
 pub struct HeroAction(pub i32, pub Vec<HeroActionVariant>);
 
 impl HeroAction {
@@ -1,5 +1,8 @@
+pub mod bitboard;
 pub mod game_context;
+pub mod game_event;
 pub mod hero;
 pub mod position;
+pub mod sim_state;
 pub mod tile;
 pub mod tilemap;
@@ -0,0 +1,295 @@
+use std::{error::Error, sync::Arc};
+
+use crate::{
+    data::{
+        bitboard::BitBoard,
+        game_context::GameContext,
+        hero::{Hero, HeroRole},
+        position::Position,
+        tile::{Occupant, TileType, TileView},
+        tilemap::TileMap,
+    },
+    infra::zobrist,
+};
+
+/// Максимум героев в симуляции (обе стороны вместе)
+pub const MAX_HEROES: usize = 16;
+
+/// Неизменная часть карты, общая для всех узлов симуляции.
+#[derive(Debug)]
+pub struct StaticMap {
+    pub width: usize,
+    pub height: usize,
+    pub tiles: Vec<TileType>,
+    /// Пустые (проходимые) клетки
+    pub passable: BitBoard,
+    /// Вклад карты в zobrist-хеш
+    pub hash: u64,
+}
+
+impl StaticMap {
+    pub fn from_tilemap(tilemap: &TileMap) -> Self {
+        Self {
+            width: tilemap.get_width(),
+            height: tilemap.get_height(),
+            tiles: tilemap.tiles.iter().map(|x| x.tile_type).collect(),
+            passable: tilemap.passable(),
+            hash: zobrist::tilemap_hash(tilemap),
+        }
+    }
+
+    #[inline]
+    pub fn index(&self, position: &Position) -> usize {
+        position.y * self.width + position.x
+    }
+
+    pub fn out_of_bounds(&self, x: i32, y: i32) -> bool {
+        x < 0 || x >= self.width as i32 || y < 0 || y >= self.height as i32
+    }
+
+    pub fn tile_at(&self, x: i32, y: i32) -> Option<TileType> {
+        if self.out_of_bounds(x, y) {
+            return None;
+        }
+        self.tiles
+            .get(y as usize * self.width + x as usize)
+            .copied()
+    }
+}
+
+/// Герой в симуляции: только то, что нужно правилам, в компактном виде.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SimHero {
+    pub agent_id: i32,
+    pub player: i32,
+    pub is_owner: bool,
+    pub alive: bool,
+    pub x: u8,
+    pub y: u8,
+    pub cooldown: i16,
+    pub wetness: i16,
+    pub splash_bombs: i16,
+    pub shoot_cooldown: i16,
+    pub optimal_range: i16,
+    pub soaking_power: i16,
+    /// Прячется в текущем ходе
+    pub hunkered: bool,
+    pub role: HeroRole,
+}
+
+impl SimHero {
+    pub fn from_hero(hero: &Hero) -> Self {
+        Self {
+            agent_id: hero.agent_id,
+            player: hero.player,
+            is_owner: hero.is_owner,
+            alive: hero.alive,
+            x: hero.position.x as u8,
+            y: hero.position.y as u8,
+            cooldown: hero.cooldown as i16,
+            wetness: hero.wetness as i16,
+            splash_bombs: hero.splash_bombs as i16,
+            shoot_cooldown: hero.shoot_cooldown as i16,
+            optimal_range: hero.optimal_range as i16,
+            soaking_power: hero.soaking_power as i16,
+            hunkered: false,
+            role: hero.role,
+        }
+    }
+
+    pub fn to_hero(&self) -> Hero {
+        Hero {
+            is_owner: self.is_owner,
+            agent_id: self.agent_id,
+            player: self.player,
+            soaking_power: self.soaking_power as i32,
+            shoot_cooldown: self.shoot_cooldown as i32,
+            optimal_range: self.optimal_range as i32,
+            splash_bombs: self.splash_bombs as i32,
+            position: self.position(),
+            cooldown: self.cooldown as i32,
+            wetness: self.wetness as i32,
+            alive: self.alive,
+            role: self.role,
+        }
+    }
+
+    #[inline]
+    pub fn position(&self) -> Position {
+        Position {
+            x: self.x as usize,
+            y: self.y as usize,
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        if !self.alive {
+            return 0;
+        }
+        zobrist::hero_parts_hash(
+            self.agent_id,
+            &self.position(),
+            self.wetness as i32,
+            self.cooldown as i32,
+            self.splash_bombs as i32,
+        )
+    }
+}
+
+/// Компактное состояние для симуляции и поиска: карта за `Arc`,
+/// герои в массиве фиксированного размера, занятость — битовая маска.
+#[derive(Debug, Clone)]
+pub struct SimState {
+    pub map: Arc<StaticMap>,
+    pub player_id: i32,
+    heroes: [SimHero; MAX_HEROES],
+    count: usize,
+    occupancy: BitBoard,
+    /// Совпадает с `GameContext::hash` для того же состояния
+    pub hash: u64,
+}
+
+impl SimState {
+    pub fn from_context(ctx: &GameContext) -> Result<Self, Box<dyn Error>> {
+        Self::with_map(Arc::new(StaticMap::from_tilemap(&ctx.tilemap)), ctx)
+    }
+
+    /// Как `from_context`, но переиспользует уже построенную карту.
+    /// Err, если карта не влезает в `BitBoard` или героев больше `MAX_HEROES`.
+    pub fn with_map(map: Arc<StaticMap>, ctx: &GameContext) -> Result<Self, Box<dyn Error>> {
+        if !BitBoard::fits(map.width, map.height) {
+            return Err(format!("map {}x{} does not fit BitBoard", map.width, map.height).into());
+        }
+        if ctx.hero_store.heroes.len() > MAX_HEROES {
+            return Err(format!(
+                "{} heroes, SimState holds {}",
+                ctx.hero_store.heroes.len(),
+                MAX_HEROES
+            )
+            .into());
+        }
+        let mut state = Self {
+            occupancy: BitBoard::empty(map.width, map.height),
+            map,
+            player_id: ctx.player_id,
+            heroes: [SimHero::default(); MAX_HEROES],
+            count: 0,
+            hash: 0,
+        };
+        for hero in &ctx.hero_store.heroes {
+            state.heroes[state.count] = SimHero::from_hero(hero);
+            state.count += 1;
+            if hero.alive {
+                state.occupy(&hero.position);
+            }
+        }
+        state.rehash();
+        Ok(state)
+    }
+
+    pub fn to_context(&self) -> GameContext {
+        let mut ctx = GameContext::new();
+        ctx.player_id = self.player_id;
+        ctx.tilemap = TileMap::new(self.map.width, self.map.height);
+
+        for (index, tile_type) in self.map.tiles.iter().enumerate() {
+            ctx.tilemap.tiles.push(TileView {
+                position: Position {
+                    x: index % self.map.width,
+                    y: index / self.map.width,
+                },
+                occupant: Occupant::Nil,
+                tile_type: *tile_type,
+            });
+        }
+
+        for hero in self.heroes() {
+            ctx.hero_store.push(hero.to_hero());
+            if !hero.alive {
+                continue;
+            }
+            if let Some(tile) = ctx.tilemap.get_tile_mut(&hero.position()) {
+                tile.occupant = if hero.player == self.player_id {
+                    Occupant::Owner(hero.agent_id as usize)
+                } else {
+                    Occupant::Enemy(hero.agent_id as usize)
+                };
+            }
+        }
+        ctx.hash = self.hash;
+        ctx
+    }
+
+    pub fn rehash(&mut self) {
+        self.hash = self
+            .heroes()
+            .iter()
+            .fold(self.map.hash, |hash, hero| hash ^ hero.hash());
+    }
+
+    /// Все герои, включая выбывших.
+    pub fn heroes(&self) -> &[SimHero] {
+        &self.heroes[..self.count]
+    }
+
+    pub fn alive(&self) -> impl Iterator<Item = &SimHero> {
+        self.heroes().iter().filter(|x| x.alive)
+    }
+
+    pub fn index_of(&self, agent_id: i32) -> Option<usize> {
+        self.heroes().iter().position(|x| x.agent_id == agent_id)
+    }
+
+    pub fn hero(&self, agent_id: i32) -> Option<&SimHero> {
+        self.heroes().iter().find(|x| x.agent_id == agent_id)
+    }
+
+    /// Изменяет героя и поддерживает хеш и занятость в согласованном виде.
+    pub fn update_hero<F: FnOnce(&mut SimHero)>(&mut self, agent_id: i32, f: F) -> bool {
+        let Some(index) = self.index_of(agent_id) else {
+            return false;
+        };
+        let before = self.heroes[index];
+        f(&mut self.heroes[index]);
+        let after = self.heroes[index];
+
+        if before.alive {
+            self.release(&before.position());
+        }
+        if after.alive {
+            self.occupy(&after.position());
+        }
+        self.hash ^= before.hash() ^ after.hash();
+        true
+    }
+
+    /// Сбрасывает HUNKER_DOWN прошлого хода.
+    pub fn clear_hunker(&mut self) {
+        for hero in self.heroes[..self.count].iter_mut() {
+            hero.hunkered = false;
+        }
+    }
+
+    pub fn occupancy(&self) -> &BitBoard {
+        &self.occupancy
+    }
+
+    pub fn is_occupied(&self, position: &Position) -> bool {
+        self.occupancy.get(position)
+    }
+
+    /// Клетка пуста и проходима.
+    pub fn is_free(&self, position: &Position) -> bool {
+        !self.map.out_of_bounds(position.x as i32, position.y as i32)
+            && self.map.passable.get(position)
+            && !self.is_occupied(position)
+    }
+
+    fn occupy(&mut self, position: &Position) {
+        self.occupancy.set(position);
+    }
+
+    fn release(&mut self, position: &Position) {
+        self.occupancy.clear(position);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::data::{
+    bitboard::BitBoard,
     position::Position,
     tile::{TileType, TileView},
 };
@@ -99,4 +100,31 @@ impl TileMap {
             .filter(|&x| x.tile_type == tile_type && !x.is_ocuped())
             .min_by_key(|x| x.position.distance(&position))
     }
+
+    pub fn tiles_where(&self, predicate: impl Fn(&TileView) -> bool) -> BitBoard {
+        let mut board = BitBoard::empty(self.width, self.height);
+        for tile in self.tiles.iter().filter(|x| predicate(x)) {
+            board.set(&tile.position);
+        }
+        board
+    }
+
+    /// Пустые клетки, по которым можно ходить (героев не учитывает).
+    pub fn passable(&self) -> BitBoard {
+        self.tiles_where(|x| x.tile_type == TileType::Empty)
+    }
+
+    /// Клетки-укрытия (низкие и высокие стены).
+    pub fn walls(&self) -> BitBoard {
+        self.tiles_where(|x| x.is_cover())
+    }
+
+    pub fn occupancy(&self) -> BitBoard {
+        self.tiles_where(|x| x.is_ocuped())
+    }
+
+    /// Свободные клетки: пустые и без героя.
+    pub fn free(&self) -> BitBoard {
+        self.tiles_where(|x| x.is_free())
+    }
 }
@@ -0,0 +1,184 @@
+use std::cell::RefCell;
+
+use crate::data::position::Position;
+
+/// Фигура отладочного слоя в координатах клеток.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Tile {
+        position: Position,
+        color: String,
+    },
+    Arrow {
+        from: Position,
+        to: Position,
+        color: String,
+    },
+    Path {
+        points: Vec<Position>,
+        color: String,
+    },
+    Text {
+        position: Position,
+        text: String,
+        color: String,
+    },
+    /// Значение тепловой карты; цвет считает визуализатор по разбросу значений слоя
+    Heat {
+        position: Position,
+        value: f32,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Layer {
+    pub name: String,
+    pub visible: bool,
+    pub shapes: Vec<Shape>,
+}
+
+/// Буфер фигур за ход, разложенных по именованным слоям.
+///
+/// Системы только складывают фигуры, рисует их визуализатор, поэтому
+/// алгоритмический код не зависит от macroquad.
+#[derive(Debug, Clone, Default)]
+pub struct DebugOverlay {
+    layers: Vec<Layer>,
+}
+
+impl DebugOverlay {
+    pub fn new() -> Self {
+        Self { layers: vec![] }
+    }
+
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    pub fn push(&mut self, layer: &str, shape: Shape) {
+        match self.layers.iter_mut().find(|x| x.name == layer) {
+            Some(found) => found.shapes.push(shape),
+            None => self.layers.push(Layer {
+                name: layer.to_string(),
+                visible: true,
+                shapes: vec![shape],
+            }),
+        }
+    }
+
+    /// Убирает фигуры всех слоёв; слои и их видимость остаются.
+    pub fn clear(&mut self) {
+        for layer in self.layers.iter_mut() {
+            layer.shapes.clear();
+        }
+    }
+
+    pub fn clear_layer(&mut self, layer: &str) {
+        if let Some(found) = self.layers.iter_mut().find(|x| x.name == layer) {
+            found.shapes.clear();
+        }
+    }
+
+    /// Неизвестные слои видимы.
+    pub fn is_visible(&self, layer: &str) -> bool {
+        self.layers
+            .iter()
+            .find(|x| x.name == layer)
+            .is_none_or(|x| x.visible)
+    }
+
+    /// Переключает видимость слоя по номеру (в порядке появления).
+    pub fn toggle(&mut self, index: usize) {
+        if let Some(layer) = self.layers.get_mut(index) {
+            layer.visible = !layer.visible;
+        }
+    }
+}
+
+thread_local! {
+    static OVERLAY: RefCell<DebugOverlay> = RefCell::new(DebugOverlay::new());
+}
+
+fn with<R>(f: impl FnOnce(&mut DebugOverlay) -> R) -> R {
+    OVERLAY.with(|overlay| f(&mut overlay.borrow_mut()))
+}
+
+pub fn push(layer: &str, shape: Shape) {
+    with(|overlay| overlay.push(layer, shape));
+}
+
+pub fn highlight<S: AsRef<str>>(layer: &str, position: &Position, color: S) {
+    push(
+        layer,
+        Shape::Tile {
+            position: *position,
+            color: color.as_ref().to_string(),
+        },
+    );
+}
+
+pub fn arrow<S: AsRef<str>>(layer: &str, from: &Position, to: &Position, color: S) {
+    push(
+        layer,
+        Shape::Arrow {
+            from: *from,
+            to: *to,
+            color: color.as_ref().to_string(),
+        },
+    );
+}
+
+pub fn path<S: AsRef<str>>(layer: &str, points: &[Position], color: S) {
+    push(
+        layer,
+        Shape::Path {
+            points: points.to_vec(),
+            color: color.as_ref().to_string(),
+        },
+    );
+}
+
+pub fn text<S: AsRef<str>, C: AsRef<str>>(layer: &str, position: &Position, text: S, color: C) {
+    push(
+        layer,
+        Shape::Text {
+            position: *position,
+            text: text.as_ref().to_string(),
+            color: color.as_ref().to_string(),
+        },
+    );
+}
+
+pub fn heat(layer: &str, position: &Position, value: f32) {
+    push(
+        layer,
+        Shape::Heat {
+            position: *position,
+            value,
+        },
+    );
+}
+
+/// Начало нового хода: фигуры прошлого хода больше не нужны.
+pub fn clear() {
+    with(|overlay| overlay.clear());
+}
+
+/// Для расчётов, которые могут повторяться в пределах хода: слой хранит последний результат.
+pub fn clear_layer(layer: &str) {
+    with(|overlay| overlay.clear_layer(layer));
+}
+
+pub fn toggle(index: usize) {
+    with(|overlay| overlay.toggle(index));
+}
+
+/// Видимость слоя в текущем буфере (для снимков прошлых ходов).
+pub fn is_visible(layer: &str) -> bool {
+    with(|overlay| overlay.is_visible(layer))
+}
+
+/// Копия буфера для отрисовки.
+pub fn snapshot() -> DebugOverlay {
+    with(|overlay| overlay.clone())
+}
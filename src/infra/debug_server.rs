@@ -0,0 +1,142 @@
+// Отладочный HTTP-сервер для `public/index.html`:
+// `GET /` отдаёт страницу визуализатора, `GET /events` — поток SSE с ходами,
+// `GET /state` — последний ход. Без фичи `debug-server` ничего не делает.
+#[cfg(feature = "debug-server")]
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::Mutex,
+    thread,
+    time::Duration,
+};
+
+use crate::data::game_context::GameContext;
+#[cfg(feature = "debug-server")]
+use crate::infra::{json, logger, scenario};
+
+/// Адрес по умолчанию (переопределяется `SOAK_DEBUG_ADDR`)
+pub const DEFAULT_ADDR: &str = "127.0.0.1:8787";
+
+#[cfg(feature = "debug-server")]
+const PAGE: &str = include_str!("../../public/index.html");
+
+/// Подписчики `/events`; пишет игровой поток, добавляет поток сервера
+#[cfg(feature = "debug-server")]
+static CLIENTS: Mutex<Vec<TcpStream>> = Mutex::new(Vec::new());
+/// Последний ход в JSON, его получает каждый новый подписчик
+#[cfg(feature = "debug-server")]
+static LAST: Mutex<Option<String>> = Mutex::new(None);
+#[cfg(feature = "debug-server")]
+static RUNNING: Mutex<bool> = Mutex::new(false);
+
+/// Запускает сервер в отдельном потоке; повторный вызов ничего не делает.
+pub fn start() {
+    #[cfg(feature = "debug-server")]
+    {
+        let mut running = RUNNING.lock().unwrap();
+        if *running {
+            return;
+        }
+        let addr = std::env::var("SOAK_DEBUG_ADDR").unwrap_or(DEFAULT_ADDR.to_string());
+        match TcpListener::bind(&addr) {
+            Ok(listener) => {
+                logger::info(format!("http://{}", addr), "debug_server::start");
+                *running = true;
+                thread::spawn(move || {
+                    for stream in listener.incoming().flatten() {
+                        if let Err(err) = handle(stream) {
+                            logger::warn(format!("{}", err), "debug_server::handle");
+                        }
+                    }
+                });
+            }
+            Err(err) => logger::error(format!("{}: {}", addr, err), "debug_server::start"),
+        }
+    }
+}
+
+pub fn is_running() -> bool {
+    #[cfg(feature = "debug-server")]
+    {
+        *RUNNING.lock().unwrap()
+    }
+    #[cfg(not(feature = "debug-server"))]
+    {
+        false
+    }
+}
+
+/// Рассылает ход подписчикам: вход referee (`scenario::to_input`) и команды отрисовки.
+pub fn publish(turn: u32, ctx: &GameContext, dsl: &str) {
+    #[cfg(feature = "debug-server")]
+    {
+        let message = format!(
+            "{{\"turn\":{},\"input\":{},\"dsl\":{}}}",
+            turn,
+            json::string(&scenario::to_input(ctx)),
+            json::string(dsl)
+        );
+        let event = sse(&message);
+        // под замком подписчиков, чтобы новый подписчик не пропустил ход
+        let mut clients = CLIENTS.lock().unwrap();
+        *LAST.lock().unwrap() = Some(message);
+        // отвалившиеся подписчики больше не нужны
+        clients.retain_mut(|client| client.write_all(event.as_bytes()).is_ok());
+    }
+    #[cfg(not(feature = "debug-server"))]
+    {
+        let _ = (turn, ctx, dsl);
+    }
+}
+
+#[cfg(feature = "debug-server")]
+fn sse(message: &str) -> String {
+    format!("event: turn\ndata: {}\n\n", message)
+}
+
+#[cfg(feature = "debug-server")]
+fn handle(mut stream: TcpStream) -> Result<(), Box<dyn std::error::Error>> {
+    let mut request = String::new();
+    BufReader::new(&stream).read_line(&mut request)?;
+    let path = request.split_whitespace().nth(1).unwrap_or("/");
+
+    match path {
+        "/" | "/index.html" => respond(&mut stream, "200 OK", "text/html; charset=utf-8", PAGE),
+        "/state" => {
+            let last = LAST.lock().unwrap().clone().unwrap_or("null".to_string());
+            respond(&mut stream, "200 OK", "application/json", &last)
+        }
+        "/events" => {
+            stream.write_all(
+                b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\nAccess-Control-Allow-Origin: *\r\n\r\n",
+            )?;
+            // медленный браузер не должен задерживать ход
+            stream.set_write_timeout(Some(Duration::from_millis(100)))?;
+            let mut clients = CLIENTS.lock().unwrap();
+            if let Some(last) = LAST.lock().unwrap().as_ref() {
+                stream.write_all(sse(last).as_bytes())?;
+            }
+            clients.push(stream);
+            Ok(())
+        }
+        _ => respond(&mut stream, "404 Not Found", "text/plain", "not found"),
+    }
+}
+
+#[cfg(feature = "debug-server")]
+fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    Ok(())
+}
@@ -1,44 +1,62 @@
-use std::{error::Error, io};
+use std::{
+    error::Error,
+    io::{self, BufRead},
+};
 
 use crate::{
     data::{
+        bitboard::BitBoard,
         game_context::GameContext,
-        hero::{Hero, HeroAction},
+        hero::{Hero, HeroAction, HeroRole},
         position::Position,
         tile::{Occupant, TileType, TileView},
         tilemap::TileMap,
     },
-    infra::logger,
+    infra::{logger, turn_clock::TurnClock},
 };
 
 macro_rules! parse_input {
     ($x:expr, $t:ident) => {
-        $x.trim().parse::<$t>().unwrap()
+        $x.trim().parse::<$t>()?
     };
 }
 
+/// Разделённая пробелами строка входа; Err, если полей меньше `count`.
+fn fields(line: &str, count: usize) -> Result<Vec<&str>, Box<dyn Error>> {
+    let inputs = line.split_whitespace().collect::<Vec<_>>();
+    if inputs.len() < count {
+        return Err(format!("expected {} fields: {:?}", count, line.trim()).into());
+    }
+    Ok(inputs)
+}
+
 pub fn read_input() -> GameContext {
+    read_input_from(&mut io::stdin().lock()).unwrap()
+}
+
+/// Блок инициализации referee из любого источника (stdin, файл сценария).
+pub fn read_input_from(reader: &mut impl BufRead) -> Result<GameContext, Box<dyn Error>> {
     let mut context = GameContext::new();
 
     let mut input_line = String::new();
-    io::stdin().read_line(&mut input_line).unwrap();
+    reader.read_line(&mut input_line)?;
     let my_id = parse_input!(input_line, i32); // Your player id (0 or 1)
 
     context.player_id = my_id;
     let mut input_line = String::new();
-    io::stdin().read_line(&mut input_line).unwrap();
+    reader.read_line(&mut input_line)?;
     let agent_data_count = parse_input!(input_line, i32); // Total number of agents in the game
     for i in 0..agent_data_count as usize {
         let mut input_line = String::new();
-        io::stdin().read_line(&mut input_line).unwrap();
-        let inputs = input_line.split(" ").collect::<Vec<_>>();
+        reader.read_line(&mut input_line)?;
+        let inputs = fields(&input_line, 6)?;
         let agent_id = parse_input!(inputs[0], i32); // Unique identifier for this agent
         let player = parse_input!(inputs[1], i32); // Player id of this agent
         let shoot_cooldown = parse_input!(inputs[2], i32); // Number of turns between each of this agent's shots
         let optimal_range = parse_input!(inputs[3], i32); // Maximum manhattan distance for greatest damage output
         let soaking_power = parse_input!(inputs[4], i32); // Damage output within optimal conditions
         let splash_bombs = parse_input!(inputs[5], i32); // Number of splash bombs this can throw this game
-        context.hero_store.heroes.push(Hero {
+        context.hero_store.push(Hero {
             is_owner: my_id == player,
             agent_id,
             shoot_cooldown,
@@ -50,19 +68,27 @@ pub fn read_input() -> GameContext {
             soaking_power,
             wetness: 0,
             alive: false,
+            role: HeroRole::classify(shoot_cooldown, optimal_range, soaking_power, splash_bombs),
         });
     }
     let mut input_line = String::new();
-    io::stdin().read_line(&mut input_line).unwrap();
-    let inputs = input_line.split(" ").collect::<Vec<_>>();
+    reader.read_line(&mut input_line)?;
+    let inputs = fields(&input_line, 2)?;
     let width = parse_input!(inputs[0], usize); // Width of the game map
     let height = parse_input!(inputs[1], usize); // Height of the game map
 
     context.tilemap = TileMap::new(width, height);
+    if !BitBoard::fits(width, height) {
+        // битовые маски увидят только первые MAX_CELLS клеток
+        logger::error(
+            format!("map {}x{} exceeds BitBoard", width, height),
+            "read_input",
+        );
+    }
     for i in 0..height as usize {
         let mut input_line = String::new();
-        io::stdin().read_line(&mut input_line).unwrap();
-        let inputs = input_line.split_whitespace().collect::<Vec<_>>();
+        reader.read_line(&mut input_line)?;
+        let inputs = fields(&input_line, 3 * width)?;
         for j in 0..width as usize {
             let x = parse_input!(inputs[3 * j], i32); // X coordinate, 0 is left edge
             let y = parse_input!(inputs[3 * j + 1], i32); // Y coordinate, 0 is top edge
@@ -78,14 +104,22 @@ pub fn read_input() -> GameContext {
             })
         }
     }
-    return context;
-
-    // game loop
+    context.rehash();
+    logger::log(
+        &context
+            .hero_store
+            .heroes
+            .iter()
+            .map(|x| (x.agent_id, x.role))
+            .collect::<Vec<_>>(),
+        "read_input:roles",
+    );
+    Ok(context)
 }
 
 pub fn read_for_loop_update(ctx: &mut GameContext) -> Result<(), Box<dyn std::error::Error>> {
 
-    ctx.hero_store.heroes.iter_mut().for_each(|hero| {
+    ctx.hero_store.alive().for_each(|hero| {
         let tile = ctx.tilemap.get_tile_mut(&hero.position).unwrap();
         if hero.player == ctx.player_id {
             tile.occupant = Occupant::Owner(hero.agent_id as usize);
@@ -97,10 +131,26 @@ pub fn read_for_loop_update(ctx: &mut GameContext) -> Result<(), Box<dyn std::er
     Ok(())
 }
 
-pub fn read_for_loop(ctx: &mut GameContext) -> Result<(), Box<dyn std::error::Error>> {
+pub fn read_for_loop(
+    ctx: &mut GameContext,
+    clock: &mut TurnClock,
+) -> Result<(), Box<dyn std::error::Error>> {
+    read_for_loop_from(&mut io::stdin().lock(), ctx, clock)
+}
+
+/// Блок одного хода referee из любого источника.
+pub fn read_for_loop_from(
+    reader: &mut impl BufRead,
+    ctx: &mut GameContext,
+    clock: &mut TurnClock,
+) -> Result<(), Box<dyn std::error::Error>> {
     logger::log_str("", "read_for_loop");
     let mut input_line = String::new();
-    io::stdin().read_line(&mut input_line)?;
+    if reader.read_line(&mut input_line)? == 0 {
+        return Err("end of input".into());
+    }
+    // лимит хода отсчитывается с первой строки
+    clock.start();
 
     ctx.tilemap.tiles.iter_mut().for_each(|tile| {
         tile.occupant = Occupant::Nil;
@@ -111,11 +161,13 @@ pub fn read_for_loop(ctx: &mut GameContext) -> Result<(), Box<dyn std::error::Er
     }
 
     let agent_count = input_line.trim().parse::<i32>()?;
+    // агенты, которых нет во входе, выбыли
+    let mut present = Vec::with_capacity(agent_count as usize);
 
     for i in 0..agent_count as usize {
         let mut input_line = String::new();
-        io::stdin().read_line(&mut input_line).unwrap();
-        let inputs = input_line.split(" ").collect::<Vec<_>>();
+        reader.read_line(&mut input_line)?;
+        let inputs = fields(&input_line, 6)?;
         let agent_id = parse_input!(inputs[0], i32);
         let x = parse_input!(inputs[1], i32);
         let y = parse_input!(inputs[2], i32);
@@ -123,12 +175,11 @@ pub fn read_for_loop(ctx: &mut GameContext) -> Result<(), Box<dyn std::error::Er
         let splash_bombs = parse_input!(inputs[4], i32);
         let wetness = parse_input!(inputs[5], i32); // Damage (0-100) this agent has taken
 
+        present.push(agent_id);
         let agent = ctx
             .hero_store
-            .heroes
-            .iter_mut()
-            .find(|x| x.agent_id == agent_id)
-            .unwrap();
+            .get_mut(agent_id)
+            .ok_or(format!("unknown agent {}", agent_id))?;
 
         agent.position = Position {
             x: x as usize,
@@ -137,8 +188,10 @@ pub fn read_for_loop(ctx: &mut GameContext) -> Result<(), Box<dyn std::error::Er
         agent.splash_bombs = splash_bombs;
         agent.cooldown = cooldown;
         agent.wetness = wetness;
-        agent.alive = true;
-        let tile_mut = ctx.tilemap.get_tile_mut(&agent.position).unwrap();
+        let tile_mut = ctx
+            .tilemap
+            .get_tile_mut(&agent.position)
+            .ok_or(format!("agent {} outside the map", agent_id))?;
 
         if agent.player == ctx.player_id {
             tile_mut.occupant = Occupant::Owner(agent.agent_id as usize);
@@ -147,7 +200,12 @@ pub fn read_for_loop(ctx: &mut GameContext) -> Result<(), Box<dyn std::error::Er
         }
     }
     let mut input_line = String::new();
-    io::stdin().read_line(&mut input_line).unwrap();
+    reader.read_line(&mut input_line)?;
     let my_agent_count = parse_input!(input_line, i32); // Number of alive agents controlled by you
+    ctx.hero_store.update_alive(&present);
+    if !ctx.hero_store.died_this_turn().is_empty() {
+        logger::log(ctx.hero_store.died_this_turn(), "read_for_loop:died");
+    }
+    ctx.rehash();
     Ok(())
 }
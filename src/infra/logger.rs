@@ -1,45 +1,267 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::Write,
+    sync::{Mutex, MutexGuard, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-pub const VERBOSE_LOGS: bool = true;
+use crate::infra::json;
 
-pub fn log<T>(value: &T, func: &str)
-where
-    T: std::fmt::Debug + ?Sized,
-{
-    if !VERBOSE_LOGS {
-        return;
+/// Уровень по умолчанию, если `SOAK_LOG` не задан
+pub const DEFAULT_LEVEL: Level = Level::Info;
+/// Сколько последних записей держим в памяти для визуализатора
+pub const RING_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    /// Только для фильтров: выключает вывод
+    Off,
+}
+
+impl Level {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Trace => "TRACE",
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warn => "WARN",
+            Level::Error => "ERROR",
+            Level::Off => "OFF",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Level> {
+        match value.trim().to_lowercase().as_str() {
+            "trace" => Some(Level::Trace),
+            "debug" => Some(Level::Debug),
+            "info" => Some(Level::Info),
+            "warn" => Some(Level::Warn),
+            "error" => Some(Level::Error),
+            "off" => Some(Level::Off),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    /// JSON lines с номером хода в каждой записи
+    Json,
+}
+
+#[derive(Debug, Clone)]
+pub struct LogRecord {
+    pub turn: u32,
+    pub timestamp: u128,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+impl LogRecord {
+    pub fn to_text(&self) -> String {
+        let total_seconds = (self.timestamp / 1000) as u64;
+        format!(
+            "[{:02}:{:02}:{:02}:{:03}] [{}] [turn {}] [{}] {}",
+            (total_seconds / 3600) % 24,
+            (total_seconds / 60) % 60,
+            total_seconds % 60,
+            self.timestamp % 1000,
+            self.level.as_str(),
+            self.turn,
+            self.target,
+            self.message
+        )
     }
 
-    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-    let total_seconds = now.as_secs();
-    let hours = (total_seconds / 3600) % 24;
-    let minutes = (total_seconds / 60) % 60;
-    let seconds = total_seconds % 60;
-    let millis = now.subsec_millis();
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"turn\":{},\"ts\":{},\"level\":{},\"target\":{},\"message\":{}}}",
+            self.turn,
+            self.timestamp,
+            json::string(self.level.as_str()),
+            json::string(&self.target),
+            json::string(&self.message)
+        )
+    }
+}
 
-    println!(
-        "[{:02}:{:02}:{:02}:{:03}] [DEBUG] [{}] {:?}",
-        hours, minutes, seconds, millis, func, value
-    );
+/// Настройки и приёмники логов.
+///
+/// Конфигурация из окружения:
+/// `SOAK_LOG=info,AiSystem=trace,find_path=off` — уровень и фильтры по префиксу цели,
+/// `SOAK_LOG_FILE=game.log`, `SOAK_LOG_FORMAT=json`, `SOAK_LOG_STDERR=0`.
+#[derive(Debug)]
+pub struct Logger {
+    level: Level,
+    filters: Vec<(String, Level)>,
+    format: Format,
+    stderr: bool,
+    file: Option<File>,
+    ring: VecDeque<LogRecord>,
+    turn: u32,
 }
 
-pub fn log_str<S: AsRef<str>>(value: S, func: &str)
+impl Logger {
+    pub fn new(level: Level) -> Self {
+        Self {
+            level,
+            filters: vec![],
+            format: Format::Text,
+            stderr: true,
+            file: None,
+            ring: VecDeque::with_capacity(RING_CAPACITY),
+            turn: 0,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let mut logger = Logger::new(DEFAULT_LEVEL);
+
+        if let Ok(spec) = std::env::var("SOAK_LOG") {
+            logger.apply_spec(&spec);
+        }
+        if let Ok(path) = std::env::var("SOAK_LOG_FILE") {
+            match File::create(&path) {
+                Ok(file) => logger.file = Some(file),
+                Err(err) => eprintln!("[logger] can't open {}: {}", path, err),
+            }
+        }
+        if std::env::var("SOAK_LOG_FORMAT").is_ok_and(|x| x.eq_ignore_ascii_case("json")) {
+            logger.format = Format::Json;
+        }
+        if std::env::var("SOAK_LOG_STDERR").is_ok_and(|x| x == "0") {
+            logger.stderr = false;
+        }
+        logger
+    }
+
+    /// Разбирает строку вида `info,AiSystem=trace`.
+    pub fn apply_spec(&mut self, spec: &str) {
+        for part in spec.split(',').filter(|x| !x.trim().is_empty()) {
+            match part.split_once('=') {
+                Some((target, level)) => {
+                    if let Some(level) = Level::parse(level) {
+                        self.filters.push((target.trim().to_string(), level));
+                    }
+                }
+                None => {
+                    if let Some(level) = Level::parse(part) {
+                        self.level = level;
+                    }
+                }
+            }
+        }
+        // самый длинный префикс проверяется первым
+        self.filters
+            .sort_by_key(|(target, _)| std::cmp::Reverse(target.len()));
+    }
+
+    pub fn enabled(&self, level: Level, target: &str) -> bool {
+        let threshold = self
+            .filters
+            .iter()
+            .find(|(prefix, _)| target.starts_with(prefix.as_str()))
+            .map(|(_, level)| *level)
+            .unwrap_or(self.level);
+        level != Level::Off && level >= threshold
+    }
+
+    pub fn write(&mut self, level: Level, target: &str, message: String) {
+        let record = LogRecord {
+            turn: self.turn,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|x| x.as_millis())
+                .unwrap_or(0),
+            level,
+            target: target.to_string(),
+            message,
+        };
+
+        let line = match self.format {
+            Format::Text => record.to_text(),
+            Format::Json => record.to_json(),
+        };
+        if self.stderr {
+            eprintln!("{}", line);
+        }
+        if let Some(file) = self.file.as_mut() {
+            let _ = writeln!(file, "{}", line);
+        }
+
+        if self.ring.len() == RING_CAPACITY {
+            self.ring.pop_front();
+        }
+        self.ring.push_back(record);
+    }
+}
+
+fn instance() -> MutexGuard<'static, Logger> {
+    static LOGGER: OnceLock<Mutex<Logger>> = OnceLock::new();
+    LOGGER
+        .get_or_init(|| Mutex::new(Logger::from_env()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Номер хода, которым помечаются следующие записи.
+pub fn set_turn(turn: u32) {
+    instance().turn = turn;
+}
+
+pub fn enabled(level: Level, target: &str) -> bool {
+    instance().enabled(level, target)
+}
+
+/// Последние `count` записей из кольцевого буфера, от старых к новым.
+pub fn recent(count: usize) -> Vec<LogRecord> {
+    let logger = instance();
+    let skip = logger.ring.len().saturating_sub(count);
+    logger.ring.iter().skip(skip).cloned().collect()
+}
+
+pub fn write<S: AsRef<str>>(level: Level, value: S, func: &str) {
+    let mut logger = instance();
+    if logger.enabled(level, func) {
+        logger.write(level, func, value.as_ref().to_string());
+    }
+}
+
+pub fn trace<S: AsRef<str>>(value: S, func: &str) {
+    write(Level::Trace, value, func);
+}
+
+pub fn info<S: AsRef<str>>(value: S, func: &str) {
+    write(Level::Info, value, func);
+}
+
+pub fn warn<S: AsRef<str>>(value: S, func: &str) {
+    write(Level::Warn, value, func);
+}
+
+pub fn error<S: AsRef<str>>(value: S, func: &str) {
+    write(Level::Error, value, func);
+}
+
+/// Отладочная запись значения через `{:?}`.
+pub fn log<T>(value: &T, func: &str)
 where
-    S: std::fmt::Debug,
+    T: std::fmt::Debug + ?Sized,
 {
-    if !VERBOSE_LOGS {
-        return;
+    let mut logger = instance();
+    if logger.enabled(Level::Debug, func) {
+        logger.write(Level::Debug, func, format!("{:?}", value));
     }
+}
 
-    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-    let total_seconds = now.as_secs();
-    let hours = (total_seconds / 3600) % 24;
-    let minutes = (total_seconds / 60) % 60;
-    let seconds = total_seconds % 60;
-    let millis = now.subsec_millis();
-
-    println!(
-        "[{:02}:{:02}:{:02}:{:03}] [DEBUG] [{}] {:?}",
-        hours, minutes, seconds, millis, func, value
-    );
+pub fn log_str<S: AsRef<str>>(value: S, func: &str) {
+    write(Level::Debug, value, func);
 }
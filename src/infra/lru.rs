@@ -1,52 +1,201 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::HashMap;
 use std::hash::Hash;
 
-#[derive(Debug, Default)]
+/// Ёмкость кеша, созданного через `Default`
+pub const DEFAULT_CAPACITY: usize = 16;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Записи, выброшенные из-за смены поколения
+    pub expired: u64,
+    /// Записи, вытесненные по ёмкости
+    pub evicted: u64,
+}
+
+impl CacheStats {
+    pub fn hit_rate(&self) -> f32 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            return 0.0;
+        }
+        self.hits as f32 / total as f32
+    }
+}
+
+#[derive(Debug)]
+struct Entry<K, V> {
+    key: K,
+    value: V,
+    generation: u64,
+    prev: Option<usize>,
+    next: Option<usize>,
+}
+
+/// LRU с O(1) get/put: записи лежат в слабе и связаны двусвязным списком.
+///
+/// Каждая запись помечена поколением (номер хода, хеш `GameContext` и т.п.),
+/// при чтении с другим поколением запись считается протухшей и удаляется.
+#[derive(Debug)]
 pub struct LruCache<K, V> {
     capacity: usize,
-    map: HashMap<K, V>,
-    order: VecDeque<K>,
+    map: HashMap<K, usize>,
+    slab: Vec<Option<Entry<K, V>>>,
+    free: Vec<usize>,
+    /// Самая свежая запись
+    head: Option<usize>,
+    /// Самая старая запись
+    tail: Option<usize>,
+    stats: CacheStats,
 }
 
 impl<K: Eq + Hash + Clone, V> LruCache<K, V> {
     pub fn new(capacity: usize) -> Self {
         Self {
-            capacity,
-            map: HashMap::new(),
-            order: VecDeque::new(),
+            capacity: capacity.max(1),
+            map: HashMap::with_capacity(capacity),
+            slab: Vec::with_capacity(capacity),
+            free: vec![],
+            head: None,
+            tail: None,
+            stats: CacheStats::default(),
         }
     }
 
-    pub fn get(&mut self, key: &K) -> Option<&V> {
-        if self.map.contains_key(key) {
-            // обновляем порядок
-            self.order.retain(|k| k != key);
-            self.order.push_back(key.clone());
-            self.map.get(key)
-        } else {
-            None
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Значение по ключу, если оно построено для того же поколения.
+    pub fn get(&mut self, key: &K, generation: u64) -> Option<&V> {
+        let Some(&index) = self.map.get(key) else {
+            self.stats.misses += 1;
+            return None;
+        };
+
+        if self.entry(index).generation != generation {
+            self.remove(key);
+            self.stats.expired += 1;
+            self.stats.misses += 1;
+            return None;
         }
+
+        self.stats.hits += 1;
+        // обновляем порядок
+        self.detach(index);
+        self.attach_front(index);
+        Some(&self.entry(index).value)
     }
 
-    pub fn put(&mut self, key: K, value: V) {
-        if self.map.contains_key(&key) {
-            self.order.retain(|k| k != &key);
-        } else if self.map.len() == self.capacity {
+    pub fn put(&mut self, key: K, value: V, generation: u64) {
+        if let Some(&index) = self.map.get(&key) {
+            let entry = self.entry_mut(index);
+            entry.value = value;
+            entry.generation = generation;
+            self.detach(index);
+            self.attach_front(index);
+            return;
+        }
+
+        if self.map.len() == self.capacity {
             // удаляем старый
-            if let Some(old) = self.order.pop_front() {
-                self.map.remove(&old);
+            if let Some(tail) = self.tail {
+                let old = self.entry(tail).key.clone();
+                self.remove(&old);
+                self.stats.evicted += 1;
             }
         }
-        self.order.push_back(key.clone());
-        self.map.insert(key, value);
+
+        let entry = Entry {
+            key: key.clone(),
+            value,
+            generation,
+            prev: None,
+            next: None,
+        };
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.slab[index] = Some(entry);
+                index
+            }
+            None => {
+                self.slab.push(Some(entry));
+                self.slab.len() - 1
+            }
+        };
+        self.attach_front(index);
+        self.map.insert(key, index);
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let index = self.map.remove(key)?;
+        self.detach(index);
+        self.free.push(index);
+        self.slab[index].take().map(|entry| entry.value)
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.slab.clear();
+        self.free.clear();
+        self.head = None;
+        self.tail = None;
+    }
+
+    fn entry(&self, index: usize) -> &Entry<K, V> {
+        self.slab[index].as_ref().expect("lru: dangling index")
+    }
+
+    fn entry_mut(&mut self, index: usize) -> &mut Entry<K, V> {
+        self.slab[index].as_mut().expect("lru: dangling index")
+    }
+
+    fn detach(&mut self, index: usize) {
+        let (prev, next) = {
+            let entry = self.entry(index);
+            (entry.prev, entry.next)
+        };
+        match prev {
+            Some(prev) => self.entry_mut(prev).next = next,
+            None => self.head = next,
+        }
+        match next {
+            Some(next) => self.entry_mut(next).prev = prev,
+            None => self.tail = prev,
+        }
+        let entry = self.entry_mut(index);
+        entry.prev = None;
+        entry.next = None;
+    }
+
+    fn attach_front(&mut self, index: usize) {
+        let head = self.head;
+        {
+            let entry = self.entry_mut(index);
+            entry.prev = None;
+            entry.next = head;
+        }
+        if let Some(head) = head {
+            self.entry_mut(head).prev = Some(index);
+        }
+        self.head = Some(index);
+        if self.tail.is_none() {
+            self.tail = Some(index);
+        }
     }
 }
 
-// fn main() {
-//     let mut cache = LruCache::new(2);
-//     cache.put(1, "one");
-//     cache.put(2, "two");
-//     println!("{:?}", cache.get(&1)); // Some("one")
-//     cache.put(3, "three"); // удалится 2
-//     println!("{:?}", cache.get(&2)); // None
-// }
+impl<K: Eq + Hash + Clone, V> Default for LruCache<K, V> {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
@@ -1,8 +1,16 @@
+pub mod debug_overlay;
+pub mod debug_server;
 pub mod input_reader;
+pub mod json;
 pub mod logger;
 pub mod lru;
 pub mod pathfinder;
+pub mod png;
 pub mod profiler;
+pub mod scenario;
+pub mod snapshot;
 pub mod storage;
 pub mod position_utils;
 pub mod simulator;
+pub mod turn_clock;
+pub mod zobrist;
@@ -1,33 +1,30 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 
 use crate::{
-    data::{game_context::GameContext, position::Position},
-    infra::logger,
+    data::{bitboard::BitBoard, game_context::GameContext, position::Position},
+    infra::{logger, profiler::Profiler},
 };
 
 pub fn can_reach(ctx: &GameContext, start: &Position, goal: &Position) -> bool {
     logger::log_str(format!("Start:{:?} Goal:{:?}", start, goal), "can_reach");
 
-    use std::collections::VecDeque;
-    let mut visited = std::collections::HashSet::new();
-    let mut queue = VecDeque::new();
-    queue.push_back(start.clone());
-    while let Some(pos) = queue.pop_front() {
-        if pos == goal.clone() {
-            return true;
-        }
-        if !visited.insert(pos) {
-            continue;
-        }
-        for next in ctx.tilemap.neighbors(&pos) {
-            if ctx.tilemap.get_tile(&next).is_some_and(|x| x.is_free()) {
-                queue.push_back(next);
-            }
-        }
-    }
-    false
+    let (width, height) = (ctx.tilemap.get_width(), ctx.tilemap.get_height());
+    BitBoard::single(width, height, start)
+        .flood_fill(&ctx.tilemap.free())
+        .get(goal)
 }
 pub fn find_path(ctx: &GameContext, start: &Position, goal: &Position) -> Option<Vec<Position>> {
+    find_path_on(ctx, &ctx.tilemap.free(), start, goal)
+}
+
+/// Как `find_path`, но проходимость задаётся явно (например, свои герои не мешают).
+pub fn find_path_on(
+    ctx: &GameContext,
+    passable: &BitBoard,
+    start: &Position,
+    goal: &Position,
+) -> Option<Vec<Position>> {
+    let _prof = Profiler::record("find_path");
     logger::log_str(format!("Start:{:?} Goal:{:?}", start, goal), "find_path");
 
     let mut visited = HashSet::new();
@@ -49,8 +46,7 @@ pub fn find_path(ctx: &GameContext, start: &Position, goal: &Position) -> Option
             continue;
         }
         for next in ctx.tilemap.neighbors(&pos) {
-            if ctx.tilemap.get_tile(&next).is_some_and(|x| x.is_free()) && !visited.contains(&next)
-            {
+            if passable.get(&next) && !visited.contains(&next) {
                 parents.insert(next, pos);
                 queue.push_back(next);
             }
@@ -0,0 +1,170 @@
+/// Минимальный кодировщик PNG (RGBA, 8 бит на канал) без внешних зависимостей.
+///
+/// Строки кодируются фильтром Sub, поэтому одноцветные области превращаются в нули,
+/// а deflate с фиксированным Хаффманом сжимает их повторами на расстоянии 1.
+pub fn encode(width: usize, height: usize, rgba: &[u8]) -> Vec<u8> {
+    assert_eq!(rgba.len(), width * height * 4, "png::encode: размер буфера");
+
+    let mut header = vec![];
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // 8 бит, RGBA, deflate, стандартные фильтры, без чересстрочности
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut raw = Vec::with_capacity((width * 4 + 1) * height);
+    for row in rgba.chunks(width * 4) {
+        raw.push(1);
+        for (i, byte) in row.iter().enumerate() {
+            raw.push(if i < 4 {
+                *byte
+            } else {
+                byte.wrapping_sub(row[i - 4])
+            });
+        }
+    }
+
+    let mut out = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+    chunk(&mut out, b"IHDR", &header);
+    chunk(&mut out, b"IDAT", &zlib(&raw));
+    chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+fn chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut table = [0u32; 256];
+    for (n, entry) in table.iter_mut().enumerate() {
+        let mut c = n as u32;
+        for _ in 0..8 {
+            c = if c & 1 == 1 {
+                0xEDB8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+        }
+        *entry = c;
+    }
+    !data.iter().fold(!0u32, |crc, byte| {
+        table[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), byte| {
+        let a = (a + *byte as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+    (b << 16) | a
+}
+
+fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    out.extend(deflate(data));
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+/// Базовые длины повторов для кодов 257..=285 и число доп. бит
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u32; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const MAX_RUN: usize = 258;
+
+/// Один блок deflate с фиксированными кодами; сжимаются только повторы предыдущего байта.
+fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut bits = BitWriter::default();
+    // последний блок, фиксированный Хаффман
+    bits.write(1, 1);
+    bits.write(1, 2);
+
+    let mut i = 0;
+    while i < data.len() {
+        let run = if i > 0 {
+            data[i..]
+                .iter()
+                .take(MAX_RUN)
+                .take_while(|x| **x == data[i - 1])
+                .count()
+        } else {
+            0
+        };
+        if run >= 3 {
+            let code = LENGTH_BASE
+                .iter()
+                .rposition(|x| *x as usize <= run)
+                .unwrap();
+            bits.symbol(257 + code as u16);
+            bits.write(
+                (run - LENGTH_BASE[code] as usize) as u32,
+                LENGTH_EXTRA[code],
+            );
+            // расстояние 1: код 0, пять бит
+            bits.code(0, 5);
+            i += run;
+        } else {
+            bits.symbol(data[i] as u16);
+            i += 1;
+        }
+    }
+    bits.symbol(256);
+    bits.finish()
+}
+
+#[derive(Default)]
+struct BitWriter {
+    out: Vec<u8>,
+    acc: u32,
+    len: u32,
+}
+
+impl BitWriter {
+    /// Число, младшими битами вперёд.
+    fn write(&mut self, value: u32, count: u32) {
+        for bit in 0..count {
+            self.acc |= ((value >> bit) & 1) << self.len;
+            self.len += 1;
+            if self.len == 8 {
+                self.out.push(self.acc as u8);
+                self.acc = 0;
+                self.len = 0;
+            }
+        }
+    }
+
+    /// Код Хаффмана, старшими битами вперёд.
+    fn code(&mut self, code: u32, count: u32) {
+        for bit in (0..count).rev() {
+            self.write((code >> bit) & 1, 1);
+        }
+    }
+
+    /// Символ литерала/длины фиксированного алфавита.
+    fn symbol(&mut self, symbol: u16) {
+        let symbol = symbol as u32;
+        match symbol {
+            0..=143 => self.code(0x30 + symbol, 8),
+            144..=255 => self.code(0x190 + symbol - 144, 9),
+            256..=279 => self.code(symbol - 256, 7),
+            _ => self.code(0xC0 + symbol - 280, 8),
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.len > 0 {
+            self.out.push(self.acc as u8);
+        }
+        self.out
+    }
+}
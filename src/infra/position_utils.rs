@@ -1,4 +1,7 @@
-use crate::data::{game_context::GameContext, hero::Hero, position::Position};
+use crate::{
+    data::{game_context::GameContext, hero::Hero, position::Position},
+    infra::{debug_overlay, profiler::Profiler},
+};
 
 pub fn is_cover_from_any_enemy(
     position: &Position,
@@ -27,9 +30,7 @@ pub fn hero_is_protected<'a>(ctx: &'a GameContext, hero: &'a Hero) -> bool {
     // берем враговы
     let enemies: Vec<_> = ctx
         .hero_store
-        .heroes
-        .iter()
-        .filter(|x| x.player != hero.agent_id)
+        .opponents_of(hero)
         .map(|p| p.position)
         .collect();
 
@@ -48,12 +49,12 @@ pub fn hero_is_protected<'a>(ctx: &'a GameContext, hero: &'a Hero) -> bool {
 }
 
 pub fn find_cover_position<'a>(ctx: &'a GameContext, enemy_target: i32) -> Vec<Position> {
+    let _prof = Profiler::record("find_cover_position");
     let mut all_covers = vec![];
 
     let enemies: Vec<_> = ctx
         .hero_store
-        .heroes
-        .iter()
+        .alive()
         .filter(|x| x.player == enemy_target)
         .map(|p| p.position)
         .collect();
@@ -76,9 +77,9 @@ pub fn find_cover_position<'a>(ctx: &'a GameContext, enemy_target: i32) -> Vec<P
         }
     }
 
+    debug_overlay::clear_layer("covers");
     all_covers.iter().for_each(|d| {
-        // crate::viz::simple::viz_simple_debug("CIRCLE", &d, "#f5f5f5", None);
-        crate::viz::render::debug_position(ctx, d, "#f5f5f5", "R2d2");
+        debug_overlay::highlight("covers", d, "#f5f5f5");
     });
 
     return all_covers;
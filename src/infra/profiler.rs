@@ -1,45 +1,231 @@
-use std::time::{Duration, Instant};
+use std::time::Duration;
+#[cfg(feature = "profiler")]
+use std::{cell::RefCell, collections::HashMap, time::Instant};
 
+use crate::infra::json;
+
+/// Сколько замеров хранится для p95; дальше они заменяются выборкой резервуаром
+pub const RESERVOIR: usize = 256;
+
+/// Агрегированная статистика одного узла дерева профайлера.
+#[derive(Debug, Clone, Default)]
+pub struct ScopeStats {
+    /// Полный путь вида `parent/child`
+    pub path: String,
+    pub depth: usize,
+    pub count: u32,
+    pub total: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    /// Не больше `RESERVOIR` замеров, равномерная выборка из всех
+    samples: Vec<Duration>,
+}
+
+impl ScopeStats {
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+
+    pub fn p95(&self) -> Duration {
+        if self.samples.is_empty() {
+            return Duration::ZERO;
+        }
+        let mut sorted = self.samples.clone();
+        sorted.sort();
+        let index = (sorted.len() * 95).div_ceil(100) - 1;
+        sorted[index]
+    }
+
+    #[cfg(feature = "profiler")]
+    fn add(&mut self, elapsed: Duration) {
+        if self.count == 0 || elapsed < self.min {
+            self.min = elapsed;
+        }
+        self.max = self.max.max(elapsed);
+        if self.samples.len() < RESERVOIR {
+            self.samples.push(elapsed);
+        } else {
+            // алгоритм R: замер номер count попадает в выборку с вероятностью RESERVOIR / (count + 1)
+            let slot = (mix(self.count as u64) % (self.count as u64 + 1)) as usize;
+            if slot < RESERVOIR {
+                self.samples[slot] = elapsed;
+            }
+        }
+        self.count += 1;
+        self.total += elapsed;
+    }
+}
+
+/// Детерминированное перемешивание (splitmix64) вместо генератора случайных чисел.
+#[cfg(feature = "profiler")]
+fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(feature = "profiler")]
+#[derive(Default)]
+struct Registry {
+    /// Пути открытых сейчас областей
+    stack: Vec<String>,
+    scopes: HashMap<String, ScopeStats>,
+}
+
+#[cfg(feature = "profiler")]
+thread_local! {
+    static REGISTRY: RefCell<Registry> = RefCell::new(Registry::default());
+}
+
+/// Замер области видимости; вложенные замеры попадают под родителя.
+/// Без фичи `profiler` — пустая структура без накладных расходов.
 pub struct Profiler {
+    #[cfg(feature = "profiler")]
     start: Instant,
-    name: String,
+    #[cfg(feature = "profiler")]
+    path: String,
 }
 
 impl Profiler {
+    #[cfg(feature = "profiler")]
     pub fn record(name: &str) -> Self {
-        let p = Profiler {
+        let path = REGISTRY.with(|registry| {
+            let mut registry = registry.borrow_mut();
+            let path = match registry.stack.last() {
+                Some(parent) => format!("{}/{}", parent, name),
+                None => name.to_string(),
+            };
+            registry.stack.push(path.clone());
+            path
+        });
+
+        Profiler {
             start: Instant::now(),
-            name: name.to_string(),
-        };
-        p
+            path,
+        }
+    }
+
+    #[cfg(not(feature = "profiler"))]
+    #[inline(always)]
+    pub fn record(_name: &str) -> Self {
+        Profiler {}
     }
 
+    #[cfg(feature = "profiler")]
     pub fn elapsed(&self) -> Duration {
-        Instant::now() - self.start
+        self.start.elapsed()
+    }
+
+    #[cfg(not(feature = "profiler"))]
+    #[inline(always)]
+    pub fn elapsed(&self) -> Duration {
+        Duration::ZERO
     }
 }
 
-// Drop автоматически выведет время при выходе из области видимости
+// Drop закрывает область и добавляет замер в статистику
+#[cfg(feature = "profiler")]
 impl Drop for Profiler {
     fn drop(&mut self) {
         let elapsed = self.elapsed();
-        println!(
-            "[PROFILER] [{}] elapsed: {}.{:03} ms",
-            self.name,
-            elapsed.as_millis(),
-            elapsed.subsec_micros() % 1000
-        );
+        REGISTRY.with(|registry| {
+            let mut registry = registry.borrow_mut();
+            registry.stack.pop();
+            let depth = self.path.matches('/').count();
+            registry
+                .scopes
+                .entry(self.path.clone())
+                .or_insert_with(|| ScopeStats {
+                    path: self.path.clone(),
+                    depth,
+                    ..Default::default()
+                })
+                .add(elapsed);
+        });
+    }
+}
+
+/// Статистика всех областей в порядке дерева (родитель перед детьми).
+pub fn report() -> Vec<ScopeStats> {
+    #[cfg(feature = "profiler")]
+    {
+        let mut scopes: Vec<ScopeStats> =
+            REGISTRY.with(|registry| registry.borrow().scopes.values().cloned().collect());
+        scopes.sort_by(|a, b| a.path.split('/').cmp(b.path.split('/')));
+        scopes
+    }
+    #[cfg(not(feature = "profiler"))]
+    {
+        vec![]
     }
 }
 
-// // Пример использования
-// fn example_function() {
-//     let _prof = Profiler::new("example_function");
+/// Сбрасывает накопленную статистику (например, в начале хода).
+pub fn reset() {
+    #[cfg(feature = "profiler")]
+    REGISTRY.with(|registry| registry.borrow_mut().scopes.clear());
+}
 
-//     // Код, который хотим профилировать
-//     std::thread::sleep(std::time::Duration::from_millis(123));
-// }
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
 
-// fn main() {
-//     example_function();
-// }
+pub fn report_table() -> String {
+    let mut out = format!(
+        "{:<48} {:>6} {:>10} {:>8} {:>8} {:>8}\n",
+        "scope", "count", "total ms", "min ms", "max ms", "p95 ms"
+    );
+    for scope in report() {
+        let name = format!("{}{}", "  ".repeat(scope.depth), scope.name());
+        out.push_str(&format!(
+            "{:<48} {:>6} {:>10.3} {:>8.3} {:>8.3} {:>8.3}\n",
+            name,
+            scope.count,
+            millis(scope.total),
+            millis(scope.min),
+            millis(scope.max),
+            millis(scope.p95()),
+        ));
+    }
+    out
+}
+
+pub fn report_json() -> String {
+    let scopes: Vec<String> = report()
+        .iter()
+        .map(|scope| {
+            format!(
+                "{{\"path\":{},\"count\":{},\"total_ms\":{:.3},\"min_ms\":{:.3},\"max_ms\":{:.3},\"p95_ms\":{:.3}}}",
+                json::string(&scope.path),
+                scope.count,
+                millis(scope.total),
+                millis(scope.min),
+                millis(scope.max),
+                millis(scope.p95()),
+            )
+        })
+        .collect();
+    format!("[{}]", scopes.join(","))
+}
+
+/// Конец хода: с `SOAK_PROFILE=table|json` печатает отчёт хода в stderr.
+/// Статистика сбрасывается каждый ход, кроме `SOAK_PROFILE=game` — там она копится до `flush_game`.
+pub fn flush_turn(turn: u32) {
+    if cfg!(feature = "profiler") {
+        match std::env::var("SOAK_PROFILE").as_deref() {
+            Ok("table") => eprint!("[PROFILER] turn {}\n{}", turn, report_table()),
+            Ok("json") => eprintln!("{{\"turn\":{},\"scopes\":{}}}", turn, report_json()),
+            Ok("game") => return,
+            _ => {}
+        }
+    }
+    reset();
+}
+
+/// Конец партии: с `SOAK_PROFILE=game` печатает итог за всю партию. По умолчанию молчит.
+pub fn flush_game() {
+    if cfg!(feature = "profiler") && std::env::var("SOAK_PROFILE").as_deref() == Ok("game") {
+        eprint!("[PROFILER] game\n{}", report_table());
+    }
+}
@@ -0,0 +1,84 @@
+use std::{error::Error, fmt::Write, fs, io::Cursor};
+
+use crate::{
+    data::{game_context::GameContext, position::Position},
+    infra::{
+        input_reader::{read_for_loop_from, read_input_from},
+        turn_clock::TurnClock,
+    },
+};
+
+/// Сценарий во формате входа referee: блок инициализации (как читает `read_input`)
+/// и один ход (как читает `read_for_loop`).
+pub fn to_input(ctx: &GameContext) -> String {
+    let mut out = String::new();
+    let heroes = &ctx.hero_store.heroes;
+
+    let _ = writeln!(out, "{}", ctx.player_id);
+    let _ = writeln!(out, "{}", heroes.len());
+    for hero in heroes {
+        let _ = writeln!(
+            out,
+            "{} {} {} {} {} {}",
+            hero.agent_id,
+            hero.player,
+            hero.shoot_cooldown,
+            hero.optimal_range,
+            hero.soaking_power,
+            hero.splash_bombs
+        );
+    }
+
+    let (width, height) = (ctx.tilemap.get_width(), ctx.tilemap.get_height());
+    let _ = writeln!(out, "{} {}", width, height);
+    for y in 0..height {
+        let row: Vec<String> = (0..width)
+            .map(|x| {
+                let tile_type = ctx
+                    .tilemap
+                    .get_tile(&Position { x, y })
+                    .map(|tile| tile.tile_type)
+                    .unwrap_or_default();
+                format!("{} {} {}", x, y, i32::from(tile_type))
+            })
+            .collect();
+        let _ = writeln!(out, "{}", row.join(" "));
+    }
+
+    let _ = writeln!(out, "{}", ctx.hero_store.alive().count());
+    for hero in ctx.hero_store.alive() {
+        let _ = writeln!(
+            out,
+            "{} {} {} {} {} {}",
+            hero.agent_id,
+            hero.position.x,
+            hero.position.y,
+            hero.cooldown,
+            hero.splash_bombs,
+            hero.wetness
+        );
+    }
+    let _ = writeln!(out, "{}", ctx.hero_store.my_heroes().count());
+    out
+}
+
+/// Разбирает то, что пишет `to_input`, тем же разбором, что и вход referee;
+/// блок хода необязателен.
+pub fn from_input(text: &str) -> Result<GameContext, Box<dyn Error>> {
+    let mut reader = Cursor::new(text);
+    let mut ctx = read_input_from(&mut reader)?;
+    if !text[reader.position() as usize..].trim().is_empty() {
+        read_for_loop_from(&mut reader, &mut ctx, &mut TurnClock::default())?;
+    }
+    ctx.sync();
+    Ok(ctx)
+}
+
+pub fn save(ctx: &GameContext, path: &str) -> Result<(), Box<dyn Error>> {
+    fs::write(path, to_input(ctx))?;
+    Ok(())
+}
+
+pub fn load(path: &str) -> Result<GameContext, Box<dyn Error>> {
+    from_input(&fs::read_to_string(path)?)
+}
@@ -1,11 +1,15 @@
+use std::collections::VecDeque;
+
 use crate::{
     data::{
+        bitboard::BitBoard,
         game_context::GameContext,
-        hero::{Hero, HeroAction, HeroActionVariant},
+        hero::{Hero, HeroAction, HeroActionVariant, HUNKER_REDUCTION, MAX_WETNESS, SPLASH_DAMAGE},
         position::Position,
-        tile::Occupant,
+        sim_state::SimState,
+        tile::{Occupant, TileType},
     },
-    infra::{logger, pathfinder},
+    infra::{logger, pathfinder, zobrist},
 };
 
 pub fn simulator_action(ctx: &mut GameContext, actions: Vec<HeroAction>) -> Result<(), String> {
@@ -18,31 +22,21 @@ pub fn simulator_action(ctx: &mut GameContext, actions: Vec<HeroAction>) -> Resu
         let id = act.0;
         let loc_actions = act.1;
 
-        let heroes = ctx.hero_store.heroes.clone();
-
-        let hero_entity = heroes.iter().find(|x| x.agent_id == id);
+        let hero_entity = ctx.hero_store.get(id).cloned();
 
         for action in loc_actions {
             match action {
                 HeroActionVariant::Move(position) => {
-                    apply_move_action(ctx, position, hero_entity.clone());
+                    apply_move_action(ctx, position, hero_entity.as_ref());
                 }
                 HeroActionVariant::Throw(position) => {
-                    apply_throw_action(ctx, position, hero_entity.clone());
+                    apply_throw_action(ctx, position, hero_entity.as_ref());
                 }
                 HeroActionVariant::Shoot { id } => {
-                    let hero = ctx
-                        .hero_store
-                        .heroes
-                        .iter()
-                        .find(|x| x.agent_id == id as i32);
+                    let hero = ctx.hero_store.get(id as i32);
                 }
                 HeroActionVariant::HunkerDown => {
-                    let hero = ctx
-                        .hero_store
-                        .heroes
-                        .iter()
-                        .find(|x| x.agent_id == id as i32);
+                    let hero = ctx.hero_store.get(id as i32);
                 }
                 HeroActionVariant::Message { text } => {}
             }
@@ -52,25 +46,34 @@ pub fn simulator_action(ctx: &mut GameContext, actions: Vec<HeroAction>) -> Resu
 }
 
 pub fn apply_throw_action(ctx: &mut GameContext, position: Position, hero: Option<&Hero>) {
-    let target_hero = hero.unwrap();
+    let Some(thrower) = hero else {
+        return;
+    };
 
-    for tile in ctx
-        .tilemap
-        .tiles
+    if let Some(source) = ctx.hero_store.get_mut(thrower.agent_id) {
+        if source.splash_bombs <= 0 {
+            return;
+        }
+        let before = zobrist::hero_hash(source);
+        source.splash_bombs -= 1;
+        ctx.hash ^= before ^ zobrist::hero_hash(source);
+    }
+
+    for target in ctx
+        .hero_store
+        .heroes
         .iter_mut()
-        .filter(|x| x.position.distance_8x(&position) <= 1)
+        .filter(|x| x.alive && x.position.distance_8x(&position) <= 1)
     {
-        match tile.occupant {
-            Occupant::Enemy(id) => {
-                ctx.hero_store.heroes.retain(|x| x.agent_id == id as i32);
-                tile.occupant = Occupant::Nil;
-            }
-            Occupant::Owner(id) => {
-                ctx.hero_store.heroes.retain(|x| x.agent_id == id as i32);
+        let before = zobrist::hero_hash(target);
+        target.wetness += SPLASH_DAMAGE;
+        if target.wetness >= MAX_WETNESS {
+            target.alive = false;
+            if let Some(tile) = ctx.tilemap.get_tile_mut(&target.position) {
                 tile.occupant = Occupant::Nil;
             }
-            Occupant::Nil => continue,
-        };
+        }
+        ctx.hash ^= before ^ zobrist::hero_hash(target);
     }
 }
 
@@ -97,14 +100,19 @@ pub fn apply_move_action(
         if let Some(t) = path {
             for p in t {
                 if hero.position != p {
-                    let new_hero = ctx
-                        .hero_store
-                        .heroes
-                        .iter_mut()
-                        .find(|x| x.agent_id == hero.agent_id);
+                    let new_hero = ctx.hero_store.get_mut(hero.agent_id);
 
                     match new_hero {
                         Some(hiro) => {
+                            // у выбывшего героя нет вклада в хэш
+                            if hiro.alive {
+                                zobrist::toggle_position(
+                                    &mut ctx.hash,
+                                    hiro.agent_id,
+                                    &hiro.position,
+                                    &p,
+                                );
+                            }
                             hiro.position = p;
                             let tile = ctx.tilemap.get_tile_mut(&p);
 
@@ -125,14 +133,18 @@ pub fn apply_move_action(
             }
         }
     } else {
-        let new_hero = ctx
-            .hero_store
-            .heroes
-            .iter_mut()
-            .find(|x| x.agent_id == hero.agent_id);
+        let new_hero = ctx.hero_store.get_mut(hero.agent_id);
 
         match new_hero {
             Some(hiro) => {
+                if hiro.alive {
+                    zobrist::toggle_position(
+                        &mut ctx.hash,
+                        hiro.agent_id,
+                        &hiro.position,
+                        &position,
+                    );
+                }
                 hiro.position = position.clone();
                 let tile = ctx.tilemap.get_tile_mut(&position);
 
@@ -150,3 +162,196 @@ pub fn apply_move_action(
     };
     Ok(())
 }
+
+/// Доля урона, поглощённая укрытием цели от выстрела из `from`.
+/// `tile_at` отдаёт тип клетки или None за пределами карты.
+pub fn cover_protection(
+    tile_at: impl Fn(i32, i32) -> Option<TileType>,
+    from: &Position,
+    target: &Position,
+) -> f32 {
+    let (dx, dy) = target.dir(from);
+    let mut protection = 0.0f32;
+
+    for (x, y) in Position::DIRECTIONS {
+        // укрытие должно стоять между стрелком и целью
+        if (x != 0 && dx * x < 2) || (y != 0 && dy * y < 2) {
+            continue;
+        }
+        let cx = target.x as i32 + x;
+        let cy = target.y as i32 + y;
+        let Some(tile_type) = tile_at(cx, cy) else {
+            continue;
+        };
+        let cover = Position {
+            x: cx as usize,
+            y: cy as usize,
+        };
+        // стрелок вплотную к укрытию его игнорирует
+        if cover.distance_8x(from) <= 1 {
+            continue;
+        }
+        protection = protection.max(f32::from(tile_type));
+    }
+    protection
+}
+
+/// Урон выстрела по правилам игры (без учёта HUNKER_DOWN цели).
+pub fn shot_damage(
+    tile_at: impl Fn(i32, i32) -> Option<TileType>,
+    soaking_power: i32,
+    optimal_range: i32,
+    from: &Position,
+    target: &Position,
+) -> i32 {
+    let distance = from.distance(target);
+    let range_factor = if distance <= optimal_range {
+        1.0
+    } else if distance <= optimal_range * 2 {
+        0.5
+    } else {
+        return 0;
+    };
+    let protection = cover_protection(tile_at, from, target);
+
+    (soaking_power as f32 * range_factor * (1.0 - protection)).round() as i32
+}
+
+/// Первый шаг кратчайшего пути по пустым клеткам (герои не учитываются).
+pub fn first_step(state: &SimState, from: &Position, goal: &Position) -> Option<Position> {
+    if from == goal {
+        return None;
+    }
+    let map = &state.map;
+    let mut parents: Vec<Option<usize>> = vec![None; map.tiles.len()];
+    let start = map.index(from);
+    let target = map.index(goal);
+    let mut queue = VecDeque::from([start]);
+    parents[start] = Some(start);
+
+    while let Some(index) = queue.pop_front() {
+        if index == target {
+            // разматываем путь до клетки рядом со стартом
+            let mut current = index;
+            while let Some(parent) = parents[current] {
+                if parent == start {
+                    return Some(Position {
+                        x: current % map.width,
+                        y: current / map.width,
+                    });
+                }
+                current = parent;
+            }
+            return None;
+        }
+        let (x, y) = ((index % map.width) as i32, (index / map.width) as i32);
+        for (dx, dy) in Position::DIRECTIONS {
+            if map.tile_at(x + dx, y + dy) != Some(TileType::Empty) {
+                continue;
+            }
+            let next = (y + dy) as usize * map.width + (x + dx) as usize;
+            if parents[next].is_none() {
+                parents[next] = Some(index);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+/// Один ход по правилам игры на компактном состоянии:
+/// движение, затем укрытия, затем одновременные выстрелы и бомбы.
+pub fn simulate_state(state: &mut SimState, actions: &[HeroAction]) {
+    state.clear_hunker();
+
+    for HeroAction(agent_id, variants) in actions {
+        for variant in variants {
+            if let HeroActionVariant::Move(target) = variant {
+                let Some(hero) = state.hero(*agent_id).filter(|x| x.alive) else {
+                    continue;
+                };
+                let Some(step) = first_step(state, &hero.position(), target) else {
+                    continue;
+                };
+                // столкновение: клетку уже занял другой герой
+                if state.is_free(&step) {
+                    state.update_hero(*agent_id, |hero| {
+                        hero.x = step.x as u8;
+                        hero.y = step.y as u8;
+                    });
+                }
+            }
+            if let HeroActionVariant::HunkerDown = variant {
+                state.update_hero(*agent_id, |hero| hero.hunkered = true);
+            }
+        }
+    }
+
+    let mut damage = vec![0i32; state.heroes().len()];
+    let mut shooters = vec![];
+
+    for HeroAction(agent_id, variants) in actions {
+        let Some(source) = state.hero(*agent_id).filter(|x| x.alive).copied() else {
+            continue;
+        };
+        for variant in variants {
+            match variant {
+                HeroActionVariant::Shoot { id } => {
+                    if source.cooldown > 0 {
+                        continue;
+                    }
+                    let Some(index) = state.index_of(*id) else {
+                        continue;
+                    };
+                    let target = state.heroes()[index];
+                    let mut dealt = shot_damage(
+                        |x, y| state.map.tile_at(x, y),
+                        source.soaking_power as i32,
+                        source.optimal_range as i32,
+                        &source.position(),
+                        &target.position(),
+                    );
+                    if target.hunkered {
+                        dealt = (dealt as f32 * (1.0 - HUNKER_REDUCTION)).round() as i32;
+                    }
+                    damage[index] += dealt;
+                    shooters.push(source.agent_id);
+                }
+                HeroActionVariant::Throw(position) => {
+                    if source.splash_bombs <= 0 {
+                        continue;
+                    }
+                    state.update_hero(source.agent_id, |hero| hero.splash_bombs -= 1);
+                    let splash =
+                        BitBoard::single(state.map.width, state.map.height, position).dilate8();
+                    for (index, target) in state.heroes().iter().enumerate() {
+                        if target.alive && splash.get(&target.position()) {
+                            damage[index] += SPLASH_DAMAGE;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let ids: Vec<i32> = state.heroes().iter().map(|x| x.agent_id).collect();
+    for (index, agent_id) in ids.into_iter().enumerate() {
+        let shot = shooters.contains(&agent_id);
+        let dealt = damage[index];
+        state.update_hero(agent_id, |hero| {
+            if !hero.alive {
+                return;
+            }
+            hero.wetness += dealt as i16;
+            if hero.wetness as i32 >= MAX_WETNESS {
+                hero.alive = false;
+            }
+            hero.cooldown = if shot {
+                hero.shoot_cooldown
+            } else {
+                (hero.cooldown - 1).max(0)
+            };
+        });
+    }
+}
@@ -0,0 +1,447 @@
+use std::{error::Error, fmt::Write as _, fs};
+
+use crate::{
+    data::{game_context::GameContext, hero::MAX_WETNESS, position::Position, tile::TileType},
+    infra::{
+        debug_overlay::{DebugOverlay, Shape},
+        json, logger, png,
+    },
+};
+
+/// Сторона клетки на снимке, пикселей
+pub const TILE_SIZE: usize = 24;
+/// Длительность хода в анимированной сводке, секунд
+pub const SUMMARY_STEP: f32 = 0.5;
+
+// палитра снимков, как в окне визуализатора
+pub const BACKGROUND: &str = "#000000";
+pub const GRID: &str = "#14213D";
+pub const LOW_WALL: &str = "#669BBC";
+pub const HIGH_WALL: &str = "#003049";
+pub const MY_HERO: &str = "#FCA311";
+pub const ENEMY_HERO: &str = "#780000";
+pub const WETNESS: &str = "#3CA7D5";
+pub const HEAT: &str = "#FF0000";
+
+/// Цвет "#RRGGBB" или "#RRGGBBAA" в RGBA; непонятная строка — белый.
+pub fn parse_color(color: &str) -> [u8; 4] {
+    let s = color.trim_start_matches('#');
+    let channel = |i: usize| s.get(i..i + 2).and_then(|x| u8::from_str_radix(x, 16).ok());
+    match (channel(0), channel(2), channel(4)) {
+        (Some(r), Some(g), Some(b)) => [r, g, b, channel(6).unwrap_or(255)],
+        _ => [255, 255, 255, 255],
+    }
+}
+
+fn with_alpha(color: &str, alpha: f32) -> [u8; 4] {
+    let [r, g, b, _] = parse_color(color);
+    [r, g, b, (alpha.clamp(0.0, 1.0) * 255.0) as u8]
+}
+
+/// RGBA-буфер в памяти: рисование без окна и GPU.
+#[derive(Debug, Clone)]
+pub struct Canvas {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize, color: [u8; 4]) -> Self {
+        Self {
+            width,
+            height,
+            pixels: color.repeat(width * height),
+        }
+    }
+
+    /// Смешивает цвет с пикселем по альфе; за краем ничего не делает.
+    pub fn blend(&mut self, x: i32, y: i32, color: [u8; 4]) {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return;
+        }
+        let i = (y as usize * self.width + x as usize) * 4;
+        let alpha = color[3] as u32;
+        for (pixel, channel) in self.pixels[i..i + 3].iter_mut().zip(color) {
+            *pixel = ((channel as u32 * alpha + *pixel as u32 * (255 - alpha)) / 255) as u8;
+        }
+        self.pixels[i + 3] = 255;
+    }
+
+    pub fn fill_rect(&mut self, x: i32, y: i32, w: i32, h: i32, color: [u8; 4]) {
+        for py in y..y + h {
+            for px in x..x + w {
+                self.blend(px, py, color);
+            }
+        }
+    }
+
+    pub fn rect_lines(&mut self, x: i32, y: i32, w: i32, h: i32, color: [u8; 4]) {
+        self.fill_rect(x, y, w, 1, color);
+        self.fill_rect(x, y + h - 1, w, 1, color);
+        self.fill_rect(x, y, 1, h, color);
+        self.fill_rect(x + w - 1, y, 1, h, color);
+    }
+
+    pub fn fill_circle(&mut self, cx: i32, cy: i32, r: i32, color: [u8; 4]) {
+        for py in cy - r..=cy + r {
+            for px in cx - r..=cx + r {
+                if (px - cx).pow(2) + (py - cy).pow(2) <= r * r {
+                    self.blend(px, py, color);
+                }
+            }
+        }
+    }
+
+    /// Отрезок Брезенхема толщиной `width` пикселей.
+    pub fn line(&mut self, (x1, y1): (i32, i32), (x2, y2): (i32, i32), width: i32, color: [u8; 4]) {
+        let (dx, dy) = ((x2 - x1).abs(), -(y2 - y1).abs());
+        let (sx, sy) = ((x2 - x1).signum(), (y2 - y1).signum());
+        let (mut x, mut y, mut err) = (x1, y1, dx + dy);
+        let half = width / 2;
+        loop {
+            self.fill_rect(x - half, y - half, width.max(1), width.max(1), color);
+            if x == x2 && y == y2 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    pub fn to_png(&self) -> Vec<u8> {
+        png::encode(self.width, self.height, &self.pixels)
+    }
+}
+
+fn center(position: &Position) -> (i32, i32) {
+    (
+        (position.x * TILE_SIZE + TILE_SIZE / 2) as i32,
+        (position.y * TILE_SIZE + TILE_SIZE / 2) as i32,
+    )
+}
+
+pub fn tile_color(tile_type: TileType) -> &'static str {
+    match tile_type {
+        TileType::LowWall => LOW_WALL,
+        TileType::HighWall => HIGH_WALL,
+        TileType::Empty => BACKGROUND,
+    }
+}
+
+// Доля значения тепловой карты в разбросе значений слоя
+pub fn heat_range(shapes: &[Shape]) -> (f32, f32) {
+    shapes
+        .iter()
+        .filter_map(|shape| match shape {
+            Shape::Heat { value, .. } => Some(*value),
+            _ => None,
+        })
+        .fold((f32::MAX, f32::MIN), |(low, high), value| {
+            (low.min(value), high.max(value))
+        })
+}
+
+pub fn heat_share(value: f32, (low, high): (f32, f32)) -> f32 {
+    if high > low {
+        (value - low) / (high - low)
+    } else {
+        1.0
+    }
+}
+
+/// Карта, герои и видимые слои в растре. Текста в растре нет (нет шрифта) — он есть в SVG.
+pub fn rasterize(ctx: &GameContext, overlay: &DebugOverlay) -> Canvas {
+    let size = TILE_SIZE as i32;
+    let mut canvas = Canvas::new(
+        ctx.tilemap.get_width() * TILE_SIZE,
+        ctx.tilemap.get_height() * TILE_SIZE,
+        parse_color(BACKGROUND),
+    );
+
+    for tile in &ctx.tilemap.tiles {
+        let (x, y) = (tile.position.x as i32 * size, tile.position.y as i32 * size);
+        canvas.fill_rect(x, y, size, size, parse_color(tile_color(tile.tile_type)));
+        canvas.rect_lines(x, y, size, size, parse_color(GRID));
+    }
+
+    for hero in ctx.hero_store.alive() {
+        let (cx, cy) = center(&hero.position);
+        let color = if hero.is_owner { MY_HERO } else { ENEMY_HERO };
+        canvas.fill_circle(cx, cy, size / 3, parse_color(color));
+
+        let (x, y) = (hero.position.x as i32 * size, hero.position.y as i32 * size);
+        let share = hero.wetness.clamp(0, MAX_WETNESS) as f32 / MAX_WETNESS as f32;
+        canvas.fill_rect(x + 2, y + size - 4, size - 4, 3, parse_color(GRID));
+        canvas.fill_rect(
+            x + 2,
+            y + size - 4,
+            ((size - 4) as f32 * share) as i32,
+            3,
+            parse_color(WETNESS),
+        );
+    }
+
+    for layer in overlay.layers().iter().filter(|x| x.visible) {
+        let range = heat_range(&layer.shapes);
+        for shape in &layer.shapes {
+            match shape {
+                Shape::Tile { position, color } => canvas.fill_rect(
+                    position.x as i32 * size,
+                    position.y as i32 * size,
+                    size,
+                    size,
+                    with_alpha(color, 0.5),
+                ),
+                Shape::Arrow { from, to, color } => {
+                    let color = parse_color(color);
+                    let (x, y) = center(to);
+                    canvas.line(center(from), (x, y), 2, color);
+                    canvas.fill_circle(x, y, size / 8, color);
+                }
+                Shape::Path { points, color } => {
+                    for pair in points.windows(2) {
+                        canvas.line(center(&pair[0]), center(&pair[1]), 2, parse_color(color));
+                    }
+                }
+                Shape::Text { .. } => {}
+                Shape::Heat { position, value } => canvas.fill_rect(
+                    position.x as i32 * size,
+                    position.y as i32 * size,
+                    size,
+                    size,
+                    with_alpha(HEAT, 0.1 + heat_share(*value, range) * 0.5),
+                ),
+            }
+        }
+    }
+    canvas
+}
+
+pub fn to_png(ctx: &GameContext, overlay: &DebugOverlay) -> Vec<u8> {
+    rasterize(ctx, overlay).to_png()
+}
+
+// Содержимое снимка без обёртки <svg>, чтобы из кадров собирать сводку
+fn svg_body(ctx: &GameContext, overlay: &DebugOverlay) -> String {
+    let size = TILE_SIZE;
+    let mut out = String::new();
+
+    for tile in &ctx.tilemap.tiles {
+        let _ = writeln!(
+            out,
+            r#"<rect x="{}" y="{}" width="{size}" height="{size}" fill="{}" stroke="{GRID}"/>"#,
+            tile.position.x * size,
+            tile.position.y * size,
+            tile_color(tile.tile_type),
+        );
+    }
+
+    for hero in ctx.hero_store.alive() {
+        let (cx, cy) = center(&hero.position);
+        let color = if hero.is_owner { MY_HERO } else { ENEMY_HERO };
+        let (x, y) = (hero.position.x * size, hero.position.y * size);
+        let share = hero.wetness.clamp(0, MAX_WETNESS) as f32 / MAX_WETNESS as f32;
+        let _ = writeln!(
+            out,
+            r#"<circle cx="{cx}" cy="{cy}" r="{}" fill="{color}"/>"#,
+            size / 3
+        );
+        let _ = writeln!(
+            out,
+            r#"<rect x="{}" y="{}" width="{}" height="3" fill="{WETNESS}"/>"#,
+            x + 2,
+            y + size - 4,
+            (size - 4) as f32 * share,
+        );
+        let _ = writeln!(
+            out,
+            r#"<text x="{cx}" y="{}" font-size="10" fill="white" text-anchor="middle">{}</text>"#,
+            cy + 4,
+            hero.agent_id
+        );
+    }
+
+    for layer in overlay.layers().iter().filter(|x| x.visible) {
+        let range = heat_range(&layer.shapes);
+        let _ = writeln!(out, r#"<g class={}>"#, json::string(&layer.name));
+        for shape in &layer.shapes {
+            let _ = match shape {
+                Shape::Tile { position, color } => writeln!(
+                    out,
+                    r#"<rect x="{}" y="{}" width="{size}" height="{size}" fill="{}" fill-opacity="0.5"/>"#,
+                    position.x * size,
+                    position.y * size,
+                    color
+                ),
+                Shape::Arrow { from, to, color } => {
+                    let ((x1, y1), (x2, y2)) = (center(from), center(to));
+                    writeln!(
+                        out,
+                        r#"<line x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}" stroke="{color}" stroke-width="2"/><circle cx="{x2}" cy="{y2}" r="{}" fill="{color}"/>"#,
+                        size / 8
+                    )
+                }
+                Shape::Path { points, color } => {
+                    let points: Vec<String> = points
+                        .iter()
+                        .map(|x| {
+                            let (x, y) = center(x);
+                            format!("{},{}", x, y)
+                        })
+                        .collect();
+                    writeln!(
+                        out,
+                        r#"<polyline points="{}" fill="none" stroke="{color}" stroke-width="2"/>"#,
+                        points.join(" ")
+                    )
+                }
+                Shape::Text {
+                    position,
+                    text,
+                    color,
+                } => {
+                    let (x, y) = center(position);
+                    writeln!(
+                        out,
+                        r#"<text x="{x}" y="{}" font-size="10" fill="{color}" text-anchor="middle">{}</text>"#,
+                        y + 4,
+                        escape(text)
+                    )
+                }
+                Shape::Heat { position, value } => writeln!(
+                    out,
+                    r#"<rect x="{}" y="{}" width="{size}" height="{size}" fill="{HEAT}" fill-opacity="{:.2}"/>"#,
+                    position.x * size,
+                    position.y * size,
+                    0.1 + heat_share(*value, range) * 0.5
+                ),
+            };
+        }
+        out.push_str("</g>\n");
+    }
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn svg_document(width: usize, height: usize, body: &str) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">\n<rect width=\"100%\" height=\"100%\" fill=\"{BACKGROUND}\"/>\n{body}</svg>\n"
+    )
+}
+
+pub fn to_svg(ctx: &GameContext, overlay: &DebugOverlay) -> String {
+    svg_document(
+        ctx.tilemap.get_width() * TILE_SIZE,
+        ctx.tilemap.get_height() * TILE_SIZE,
+        &svg_body(ctx, overlay),
+    )
+}
+
+/// Записывает снимки ходов в каталог `SOAK_SNAPSHOTS` (PNG и SVG на ход)
+/// и собирает из них анимированную SVG-сводку партии.
+#[derive(Debug, Default)]
+pub struct Recorder {
+    dir: Option<String>,
+    size: (usize, usize),
+    /// (ход, тело SVG)
+    frames: Vec<(u32, String)>,
+}
+
+impl Recorder {
+    pub fn new(dir: Option<String>) -> Self {
+        Self {
+            dir,
+            size: (0, 0),
+            frames: vec![],
+        }
+    }
+
+    /// Без переменной окружения запись выключена.
+    pub fn from_env() -> Self {
+        Self::new(std::env::var("SOAK_SNAPSHOTS").ok())
+    }
+
+    pub fn record(&mut self, turn: u32, ctx: &GameContext, overlay: &DebugOverlay) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        if let Err(err) = Self::write_frame(dir, turn, ctx, overlay) {
+            logger::error(format!("{}: {}", dir, err), "Recorder::record");
+        }
+        self.size = (
+            ctx.tilemap.get_width() * TILE_SIZE,
+            ctx.tilemap.get_height() * TILE_SIZE,
+        );
+        self.frames.push((turn, svg_body(ctx, overlay)));
+    }
+
+    fn write_frame(
+        dir: &str,
+        turn: u32,
+        ctx: &GameContext,
+        overlay: &DebugOverlay,
+    ) -> Result<(), Box<dyn Error>> {
+        fs::create_dir_all(dir)?;
+        fs::write(
+            format!("{}/turn_{:03}.png", dir, turn),
+            to_png(ctx, overlay),
+        )?;
+        fs::write(
+            format!("{}/turn_{:03}.svg", dir, turn),
+            to_svg(ctx, overlay),
+        )?;
+        Ok(())
+    }
+
+    /// Кадры по очереди показываются по `SUMMARY_STEP` секунд, последний остаётся.
+    pub fn summary(&self) -> String {
+        let mut body = String::new();
+        let last = self.frames.len().saturating_sub(1);
+        for (i, (turn, frame)) in self.frames.iter().enumerate() {
+            let begin = i as f32 * SUMMARY_STEP;
+            let timing = if i == last {
+                format!(r#"begin="{begin}s" fill="freeze""#)
+            } else {
+                format!(r#"begin="{begin}s" dur="{SUMMARY_STEP}s""#)
+            };
+            let _ = writeln!(
+                body,
+                r#"<g visibility="hidden"><set attributeName="visibility" to="visible" {timing}/>"#
+            );
+            body.push_str(frame);
+            let _ = writeln!(
+                body,
+                r#"<text x="4" y="12" font-size="12" fill="white">turn {turn}</text></g>"#
+            );
+        }
+        svg_document(self.size.0, self.size.1, &body)
+    }
+
+    /// Пишет `summary.svg`; вызывать в конце партии.
+    pub fn finish(&self) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        if self.frames.is_empty() {
+            return;
+        }
+        let path = format!("{}/summary.svg", dir);
+        match fs::write(&path, self.summary()) {
+            Ok(_) => logger::info(format!("saved {}", path), "Recorder::finish"),
+            Err(err) => logger::error(format!("{}: {}", path, err), "Recorder::finish"),
+        }
+    }
+}
@@ -1 +1,184 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt,
+    marker::PhantomData,
+};
 
+/// Типизированный ключ хранилища: тип значения зашит в ключ,
+/// поэтому `get` не может вернуть значение чужого типа.
+///
+/// ```ignore
+/// pub const LAST_CONTEXT: Key<GameContext> = Key::new("last_context");
+/// ```
+pub struct Key<T> {
+    name: &'static str,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Key<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl<T: Any> Key<T> {
+    /// Одно имя с разными типами — разные записи
+    fn id(&self) -> EntryId {
+        (self.name, TypeId::of::<T>())
+    }
+}
+
+impl<T> Clone for Key<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Key<T> {}
+
+impl<T> fmt::Debug for Key<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Key({})", self.name)
+    }
+}
+
+type EntryId = (&'static str, TypeId);
+
+/// Значение, которое можно положить в хранилище и скопировать для снапшота.
+trait StoredValue: Any {
+    fn clone_box(&self) -> Box<dyn StoredValue>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<T: Any + Clone> StoredValue for T {
+    fn clone_box(&self) -> Box<dyn StoredValue> {
+        Box::new(self.clone())
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+impl Clone for Box<dyn StoredValue> {
+    fn clone(&self) -> Self {
+        (**self).clone_box()
+    }
+}
+
+/// Снимок хранилища для отката после симуляции.
+#[derive(Clone)]
+pub struct Snapshot {
+    entries: HashMap<EntryId, Box<dyn StoredValue>>,
+}
+
+/// Память между ходами в рамках одной игры.
+///
+/// Преемник `MetaContext`: вместо строковых ключей и `Any` — `Key<T>`.
+#[derive(Default)]
+pub struct Storage {
+    entries: HashMap<EntryId, Box<dyn StoredValue>>,
+    snapshots: Vec<Snapshot>,
+}
+
+impl Storage {
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            snapshots: vec![],
+        }
+    }
+
+    pub fn insert<T: Any + Clone>(&mut self, key: Key<T>, value: T) -> Option<T> {
+        let old = self.entries.insert(key.id(), Box::new(value))?;
+        old.into_any().downcast::<T>().ok().map(|x| *x)
+    }
+
+    pub fn get<T: Any + Clone>(&self, key: Key<T>) -> Option<&T> {
+        // разыменовываем явно: сам Box<dyn StoredValue> тоже StoredValue
+        (**self.entries.get(&key.id())?)
+            .as_any()
+            .downcast_ref::<T>()
+    }
+
+    pub fn get_mut<T: Any + Clone>(&mut self, key: Key<T>) -> Option<&mut T> {
+        (**self.entries.get_mut(&key.id())?)
+            .as_any_mut()
+            .downcast_mut::<T>()
+    }
+
+    /// Значение по ключу, при отсутствии создаётся через `Default`.
+    pub fn entry<T: Any + Clone + Default>(&mut self, key: Key<T>) -> &mut T {
+        if self.get::<T>(key).is_none() {
+            self.entries.insert(key.id(), Box::new(T::default()));
+        }
+        self.get_mut(key).unwrap()
+    }
+
+    pub fn remove<T: Any + Clone>(&mut self, key: Key<T>) -> Option<T> {
+        let old = self.entries.remove(&key.id())?;
+        old.into_any().downcast::<T>().ok().map(|x| *x)
+    }
+
+    pub fn contains<T: Any>(&self, key: Key<T>) -> bool {
+        self.entries.contains_key(&key.id())
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            entries: self.entries.clone(),
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        self.entries = snapshot.entries;
+    }
+
+    /// Запоминает текущее состояние перед симуляцией.
+    pub fn push_snapshot(&mut self) {
+        let snapshot = self.snapshot();
+        self.snapshots.push(snapshot);
+    }
+
+    /// Откатывает изменения с последнего `push_snapshot`.
+    pub fn rollback(&mut self) -> bool {
+        match self.snapshots.pop() {
+            Some(snapshot) => {
+                self.restore(snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Принимает изменения с последнего `push_snapshot`.
+    pub fn commit(&mut self) -> bool {
+        self.snapshots.pop().is_some()
+    }
+}
+
+impl fmt::Debug for Storage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut keys: Vec<_> = self.entries.keys().map(|(name, _)| name).collect();
+        keys.sort();
+        f.debug_struct("Storage")
+            .field("keys", &keys)
+            .field("snapshots", &self.snapshots.len())
+            .finish()
+    }
+}
@@ -0,0 +1,72 @@
+use std::time::{Duration, Instant};
+
+/// Лимит на первый ход (вместе с инициализацией)
+pub const FIRST_TURN_BUDGET: Duration = Duration::from_millis(1000);
+/// Лимит на каждый следующий ход
+pub const TURN_BUDGET: Duration = Duration::from_millis(50);
+/// Запас на вывод команд и задержки ввода-вывода
+pub const SAFETY_MARGIN: Duration = Duration::from_millis(5);
+
+/// Часы хода: запускаются с приходом первой строки ввода хода.
+#[derive(Debug, Clone)]
+pub struct TurnClock {
+    start: Instant,
+    budget: Duration,
+    first_turn: Duration,
+    later_turn: Duration,
+    turn: u32,
+}
+
+impl TurnClock {
+    pub fn new(first_turn: Duration, later_turn: Duration) -> Self {
+        Self {
+            start: Instant::now(),
+            budget: first_turn,
+            first_turn,
+            later_turn,
+            turn: 0,
+        }
+    }
+
+    /// Начинает отсчёт нового хода.
+    pub fn start(&mut self) {
+        self.start = Instant::now();
+        self.budget = if self.turn == 0 {
+            self.first_turn
+        } else {
+            self.later_turn
+        };
+        self.turn += 1;
+    }
+
+    /// Номер текущего хода, начиная с 1 (0 — часы ещё не запускались).
+    pub fn turn(&self) -> u32 {
+        self.turn
+    }
+
+    pub fn budget(&self) -> Duration {
+        self.budget
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Оставшееся время хода с учётом запаса.
+    pub fn remaining(&self) -> Duration {
+        self.budget
+            .saturating_sub(SAFETY_MARGIN)
+            .saturating_sub(self.elapsed())
+    }
+
+    /// Пора отдавать лучший найденный ответ.
+    pub fn is_expired(&self) -> bool {
+        self.remaining().is_zero()
+    }
+}
+
+impl Default for TurnClock {
+    fn default() -> Self {
+        Self::new(FIRST_TURN_BUDGET, TURN_BUDGET)
+    }
+}
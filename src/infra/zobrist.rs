@@ -0,0 +1,111 @@
+use crate::data::{
+    game_context::GameContext, hero::Hero, position::Position, tile::TileType, tilemap::TileMap,
+};
+
+const SEED: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// Компонент состояния, для которого берётся ключ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    Position = 1,
+    Wetness = 2,
+    Cooldown = 3,
+    Bombs = 4,
+    Tile = 5,
+}
+
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Псевдослучайный ключ вместо таблицы: детерминирован и не зависит от размеров карты.
+#[inline]
+pub fn key(feature: Feature, id: i32, value: i32) -> u64 {
+    splitmix64(SEED ^ ((feature as u64) << 56) ^ ((id as u32 as u64) << 32) ^ (value as u32 as u64))
+}
+
+#[inline]
+pub fn position_key(agent_id: i32, position: &Position) -> u64 {
+    key(
+        Feature::Position,
+        agent_id,
+        ((position.x as i32) << 16) | position.y as i32,
+    )
+}
+
+/// Хеш героя; мёртвые герои в состояние не входят.
+pub fn hero_hash(hero: &Hero) -> u64 {
+    if !hero.alive {
+        return 0;
+    }
+    hero_parts_hash(
+        hero.agent_id,
+        &hero.position,
+        hero.wetness,
+        hero.cooldown,
+        hero.splash_bombs,
+    )
+}
+
+/// То же, что `hero_hash`, для представлений героя помимо `Hero` (например, `SimHero`).
+pub fn hero_parts_hash(
+    agent_id: i32,
+    position: &Position,
+    wetness: i32,
+    cooldown: i32,
+    splash_bombs: i32,
+) -> u64 {
+    position_key(agent_id, position)
+        ^ key(Feature::Wetness, agent_id, wetness)
+        ^ key(Feature::Cooldown, agent_id, cooldown)
+        ^ key(Feature::Bombs, agent_id, splash_bombs)
+}
+
+pub fn tilemap_hash(tilemap: &TileMap) -> u64 {
+    tilemap
+        .tiles
+        .iter()
+        .enumerate()
+        .fold(0, |hash, (index, tile)| {
+            hash ^ tile_key(index, tile.tile_type)
+        })
+}
+
+#[inline]
+pub fn tile_key(index: usize, tile_type: TileType) -> u64 {
+    key(Feature::Tile, index as i32, i32::from(tile_type))
+}
+
+/// Полный хеш; XOR делает его независимым от порядка `HeroStore::heroes`.
+pub fn hash_context(ctx: &GameContext) -> u64 {
+    ctx.hero_store
+        .heroes
+        .iter()
+        .fold(tilemap_hash(&ctx.tilemap), |hash, hero| {
+            hash ^ hero_hash(hero)
+        })
+}
+
+/// Хеш только позиций живых героев (для кешей, зависящих от расстановки).
+pub fn positions_hash(ctx: &GameContext) -> u64 {
+    ctx.hero_store
+        .heroes
+        .iter()
+        .filter(|x| x.alive)
+        .fold(0, |hash, hero| {
+            hash ^ position_key(hero.agent_id, &hero.position)
+        })
+}
+
+// Инкрементальные обновления: XOR старого и нового ключа
+
+pub fn toggle_position(hash: &mut u64, agent_id: i32, from: &Position, to: &Position) {
+    *hash ^= position_key(agent_id, from) ^ position_key(agent_id, to);
+}
+
+pub fn toggle_value(hash: &mut u64, feature: Feature, agent_id: i32, from: i32, to: i32) {
+    *hash ^= key(feature, agent_id, from) ^ key(feature, agent_id, to);
+}
@@ -1,73 +1,42 @@
+// без окна часть отладочных помощников не используется
+#![cfg_attr(not(feature = "viewer"), allow(dead_code))]
+
 pub mod core;
 pub mod data;
 pub mod infra;
 pub mod viz;
 
-use crate::{
-    core::agg_system::AggSystem,
-    infra::{
-        input_reader::{read_for_loop, read_for_loop_update, read_input},
-        logger,
-        position_utils::find_cover_position,
-        simulator::simulator_action,
-    },
-    viz::render::{draw_heroes, draw_map, render_context},
-};
-use macroquad::prelude::*;
-
 /**
  * Win the water fight by controlling the most territory, or out-soak your opponent!
  **/
 
+#[cfg(feature = "viewer")]
 #[macroquad::main("MyGame")]
 async fn main() {
-    let mut ctx = read_input();
-    let mut agg_system = AggSystem::new();
-
-    let mut ticker = 0.0;
-    let mut iteration = 0;
-
-    // game loop
-    loop {
-        let dt = get_frame_time();
-
-        ticker += dt;
-
-        clear_background(BLACK);
-
-        draw_map(&ctx);
-        draw_heroes(&ctx);
+    viz::app::run().await;
+}
 
-        if is_key_down(KeyCode::R) {
-            find_cover_position(&ctx, 1);
+/// Бот для referee: ход за ходом stdin -> stdout, без окна и ожидания.
+#[cfg(not(feature = "viewer"))]
+fn main() {
+    use crate::{
+        core::game_loop::GameLoop,
+        infra::{debug_overlay, debug_server, input_reader::read_input},
+        viz::simple,
+    };
+
+    let mut game = GameLoop::new(read_input());
+    debug_server::start();
+
+    while game.read().is_ok() {
+        let actions = game.decide();
+        for action in &actions {
+            println!("{}", action.to_string());
         }
-
-        if ticker >= 1.0 {
-            logger::log(&iteration, "main::ticker");
-            iteration += 1;
-            match read_for_loop(&mut ctx) {
-                Ok(_) => {}
-                Err(err) => {
-                    if let Err(inner) = read_for_loop_update(&mut ctx) {
-                        eprintln!("ACTION:{}", inner);
-                    }
-                    eprintln!("{:?}", err);
-                }
-            }
-
-            let res = agg_system.process(&ctx);
-            if res.len() > 0 {
-                match simulator_action(&mut ctx, res) {
-                    Result::Ok(_) => {}
-                    Err(e_string) => panic!("{}", e_string),
-                }
-            }
-
-            ticker -= 1.0;
+        if debug_server::is_running() {
+            let lines = simple::from_overlay(&game.ctx, &debug_overlay::snapshot());
+            debug_server::publish(game.clock.turn(), &game.ctx, &simple::write(&lines));
         }
-
-        render_context(&ctx);
-
-        next_frame().await
+        game.simulate(actions);
     }
 }
@@ -0,0 +1,222 @@
+use crate::{
+    core::{agg_system::OPPONENTS, game_loop::GameLoop, opponent_model::OpponentModel},
+    infra::{
+        debug_overlay, debug_server, input_reader::read_input, logger,
+        position_utils::find_cover_position,
+    },
+    viz::{
+        editor::Editor,
+        heatmap::{draw_heatmap, HeatmapMode},
+        render::{
+            draw_actions, draw_commands, draw_events, draw_heroes, draw_log_panel, draw_map,
+            draw_overlay, draw_timeline, render_context, scrubber_seek, tile_under_mouse,
+        },
+        simple::{self, DrawingLine},
+        timeline::{Frame, Timeline},
+    },
+};
+use macroquad::prelude::*;
+
+/// Клавиши переключения отладочных слоёв, по порядку слоёв
+const LAYER_KEYS: [KeyCode; 9] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
+/// Окно визуализатора: ходы читаются по таймеру или по шагу пользователя.
+pub async fn run() {
+    let mut game = GameLoop::new(read_input());
+
+    let mut timeline = Timeline::new();
+    let mut iteration = 0;
+    let mut show_logs = false;
+    let mut heatmap = HeatmapMode::default();
+    // герой для тепловых карт угрозы, укрытий и расстояния
+    let mut selected: Option<i32> = None;
+    // ручная правка позиции; пока открыт редактор, ходы не читаются
+    let mut editor: Option<Editor> = None;
+    // файл языка отрисовки: D сохраняет текущий ход, O показывает/прячет файл
+    let dsl_path = std::env::var("SOAK_DSL").unwrap_or("overlay.dsl".to_string());
+    let mut dsl: Option<Vec<DrawingLine>> = None;
+    // номер хода, который последним ушёл в отладочный сервер
+    let mut published: Option<usize> = None;
+    // ввод referee кончился: новых ходов не будет, остаётся история
+    let mut ended = false;
+    debug_server::start();
+
+    // game loop
+    loop {
+        let dt = get_frame_time();
+
+        clear_background(BLACK);
+
+        if is_key_pressed(KeyCode::E) {
+            editor = match editor {
+                Some(_) => None,
+                None => {
+                    let shown = timeline.current().map_or(&game.ctx, |frame| &frame.ctx);
+                    Some(Editor::new(shown.clone()))
+                }
+            };
+        }
+        if let Some(editor) = editor.as_mut() {
+            editor.update();
+        }
+
+        // смотрим редактор или сохранённый ход; до первого хода — текущее состояние
+        let shown = match &editor {
+            Some(editor) => &editor.ctx,
+            None => timeline.current().map_or(&game.ctx, |frame| &frame.ctx),
+        };
+        draw_map(shown);
+        let hero = editor
+            .as_ref()
+            .map_or(selected, |x| x.selected())
+            .and_then(|id| shown.hero_store.get(id))
+            .or_else(|| shown.hero_store.my_heroes().next());
+        let no_model = OpponentModel::default();
+        let opponents = game
+            .agg_system
+            .storage()
+            .get(OPPONENTS)
+            .unwrap_or(&no_model);
+        if let Some(field) = heatmap.field(shown, opponents, hero) {
+            draw_heatmap(shown, heatmap, &field);
+        }
+        draw_heroes(shown);
+        match (&editor, timeline.current()) {
+            (None, Some(frame)) => {
+                draw_events(&frame.ctx, &frame.events);
+                draw_actions(&frame.ctx, &frame.actions);
+                draw_overlay(&frame.ctx, &frame.overlay);
+            }
+            _ => draw_overlay(shown, &debug_overlay::snapshot()),
+        }
+        if let Some(lines) = &dsl {
+            draw_commands(shown, lines);
+        }
+
+        if is_key_pressed(KeyCode::D) {
+            let overlay = timeline
+                .current()
+                .map_or_else(debug_overlay::snapshot, |frame| frame.overlay.clone());
+            match simple::dump(shown, &overlay, &dsl_path) {
+                Ok(_) => logger::info(format!("saved {}", dsl_path), "main::dsl"),
+                Err(err) => logger::error(format!("{}: {}", dsl_path, err), "main::dsl"),
+            }
+        }
+        if is_key_pressed(KeyCode::O) {
+            dsl = match dsl {
+                Some(_) => None,
+                None => match std::fs::read_to_string(&dsl_path)
+                    .map_err(|err| err.into())
+                    .and_then(|text| simple::parse(&text))
+                {
+                    Ok(lines) => Some(lines),
+                    Err(err) => {
+                        logger::error(format!("{}: {}", dsl_path, err), "main::dsl");
+                        None
+                    }
+                },
+            };
+        }
+
+        if is_key_pressed(KeyCode::H) {
+            heatmap = heatmap.next();
+        }
+        if editor.is_none() && is_mouse_button_pressed(MouseButton::Left) {
+            if let Some(position) = tile_under_mouse(shown) {
+                if let Some(hero) = shown.hero_store.alive().find(|x| x.position == position) {
+                    selected = Some(hero.agent_id);
+                }
+            }
+        }
+
+        if is_key_down(KeyCode::R) {
+            find_cover_position(&game.ctx, 1);
+        }
+        if is_key_pressed(KeyCode::L) {
+            show_logs = !show_logs;
+        }
+        for (index, key) in LAYER_KEYS.iter().enumerate() {
+            if is_key_pressed(*key) {
+                debug_overlay::toggle(index);
+            }
+        }
+
+        // управление историей: пробел — пауза, стрелки — шаги и скорость, мышь — полоса ходов
+        let mut advance = false;
+        if is_key_pressed(KeyCode::Space) {
+            timeline.toggle_pause();
+        }
+        if is_key_pressed(KeyCode::Left) {
+            timeline.step_back();
+        }
+        if is_key_pressed(KeyCode::Right) {
+            advance |= !timeline.step_forward();
+        }
+        if is_key_pressed(KeyCode::Up) {
+            timeline.faster();
+        }
+        if is_key_pressed(KeyCode::Down) {
+            timeline.slower();
+        }
+        if let Some(index) = scrubber_seek(timeline.frames().len()) {
+            timeline.seek(index);
+        }
+        if editor.is_none() && timeline.tick(dt) {
+            advance |= !timeline.step_forward();
+        }
+
+        if advance && editor.is_none() && !ended {
+            logger::log(&iteration, "main::ticker");
+            iteration += 1;
+            // ошибку чтения GameLoop уже записал; как и консольный бот, дальше не ходим
+            if game.read().is_err() {
+                ended = true;
+            } else {
+                let res = game.decide();
+                timeline.push(Frame {
+                    turn: game.clock.turn(),
+                    ctx: game.ctx.clone(),
+                    overlay: debug_overlay::snapshot(),
+                    events: game.agg_system.events().last().to_vec(),
+                    actions: res.clone(),
+                    rationale: game.agg_system.rationale().to_vec(),
+                });
+                game.simulate(res);
+            }
+        }
+
+        // браузер показывает тот же ход, что и окно: и живую игру, и просмотр истории
+        if debug_server::is_running() && published != Some(timeline.cursor()) {
+            if let Some(frame) = timeline.current() {
+                let lines = simple::from_overlay(&frame.ctx, &frame.overlay);
+                debug_server::publish(frame.turn, &frame.ctx, &simple::write(&lines));
+                published = Some(timeline.cursor());
+            }
+        }
+
+        match (&editor, timeline.current()) {
+            (Some(editor), _) => {
+                render_context(&editor.ctx, &[], editor.editable());
+                editor.draw();
+            }
+            (None, Some(frame)) => render_context(&frame.ctx, &frame.rationale, None),
+            (None, None) => render_context(&game.ctx, &[], None),
+        }
+        draw_timeline(&timeline);
+        if show_logs {
+            draw_log_panel(20);
+        }
+
+        next_frame().await
+    }
+}
@@ -0,0 +1,270 @@
+use crate::{
+    core::agg_system::AggSystem,
+    data::{
+        game_context::GameContext,
+        hero::{Hero, HeroActionVariant, HeroRole, MAX_WETNESS},
+        position::Position,
+        tile::TileType,
+    },
+    infra::{
+        debug_overlay, logger, position_utils::find_cover_position, scenario, turn_clock::TurnClock,
+    },
+    viz::render::{property_buttons, tile_under_mouse, HERO_PANEL_Y},
+};
+
+use macroquad::prelude::*;
+
+/// Файл сценария по умолчанию (переопределяется `SOAK_SCENARIO`)
+pub const DEFAULT_SCENARIO: &str = "scenario.txt";
+
+/// Поля героя, которые правятся кнопками -/+ в панели свойств
+pub const FIELDS: [&str; 3] = ["wetness", "cooldown", "splash_bombs"];
+/// Шаг кнопок для каждого из `FIELDS`
+const STEPS: [i32; 3] = [10, 1, 1];
+
+/// Ручная правка позиции в окне визуализатора.
+///
+/// Клик по клетке меняет её тип, перетаскивание двигает героя,
+/// N/M добавляют своего/вражеского героя, Delete убирает выбранного,
+/// F5/F9 сохраняют/загружают сценарий, G прогоняет системы на позиции.
+#[derive(Debug, Clone)]
+pub struct Editor {
+    pub ctx: GameContext,
+    selected: Option<i32>,
+    dragging: Option<i32>,
+    path: String,
+}
+
+impl Editor {
+    pub fn new(ctx: GameContext) -> Self {
+        Self {
+            ctx,
+            selected: None,
+            dragging: None,
+            path: std::env::var("SOAK_SCENARIO").unwrap_or(DEFAULT_SCENARIO.to_string()),
+        }
+    }
+
+    pub fn selected(&self) -> Option<i32> {
+        self.selected
+    }
+
+    /// Выбранный герой и его редактируемые поля для `render_context`.
+    pub fn editable(&self) -> Option<(i32, &'static [&'static str])> {
+        Some((self.selected?, &FIELDS))
+    }
+
+    pub fn update(&mut self) {
+        if let Some(field) = self.panel_click() {
+            self.edit_field(field);
+        } else {
+            self.mouse();
+        }
+
+        if is_key_pressed(KeyCode::N) {
+            self.add_hero(true);
+        }
+        if is_key_pressed(KeyCode::M) {
+            self.add_hero(false);
+        }
+        if is_key_pressed(KeyCode::Delete) || is_key_pressed(KeyCode::Backspace) {
+            if let Some(id) = self.selected.take() {
+                self.ctx.hero_store.remove(id);
+                self.ctx.sync();
+            }
+        }
+        if is_key_pressed(KeyCode::F5) {
+            match scenario::save(&self.ctx, &self.path) {
+                Ok(_) => logger::info(format!("saved {}", self.path), "Editor"),
+                Err(err) => logger::error(format!("{}: {}", self.path, err), "Editor"),
+            }
+        }
+        if is_key_pressed(KeyCode::F9) {
+            match scenario::load(&self.path) {
+                Ok(ctx) => {
+                    self.ctx = ctx;
+                    self.selected = None;
+                    logger::info(format!("loaded {}", self.path), "Editor");
+                }
+                Err(err) => logger::error(format!("{}: {}", self.path, err), "Editor"),
+            }
+        }
+        if is_key_pressed(KeyCode::G) {
+            self.run_systems();
+        }
+    }
+
+    fn mouse(&mut self) {
+        let Some(position) = tile_under_mouse(&self.ctx) else {
+            return;
+        };
+        let hero_at = self
+            .ctx
+            .hero_store
+            .alive()
+            .find(|x| x.position == position)
+            .map(|x| x.agent_id);
+
+        if is_mouse_button_pressed(MouseButton::Left) {
+            match hero_at {
+                Some(id) => {
+                    self.selected = Some(id);
+                    self.dragging = Some(id);
+                }
+                None => self.cycle_tile(&position),
+            }
+        }
+        if is_mouse_button_released(MouseButton::Left) {
+            if let Some(id) = self.dragging.take() {
+                let free = self
+                    .ctx
+                    .tilemap
+                    .get_tile(&position)
+                    .is_some_and(|x| x.is_free());
+                if free {
+                    if let Some(hero) = self.ctx.hero_store.get_mut(id) {
+                        hero.position = position;
+                    }
+                    self.ctx.sync();
+                }
+            }
+        }
+    }
+
+    fn cycle_tile(&mut self, position: &Position) {
+        if let Some(tile) = self.ctx.tilemap.get_tile_mut(position) {
+            tile.tile_type = match tile.tile_type {
+                TileType::Empty => TileType::LowWall,
+                TileType::LowWall => TileType::HighWall,
+                TileType::HighWall => TileType::Empty,
+            };
+        }
+        self.ctx.sync();
+    }
+
+    /// Новый герой под курсором с характеристиками стрелка.
+    fn add_hero(&mut self, is_owner: bool) {
+        let Some(position) = tile_under_mouse(&self.ctx) else {
+            return;
+        };
+        if !self
+            .ctx
+            .tilemap
+            .get_tile(&position)
+            .is_some_and(|x| x.is_free())
+        {
+            return;
+        }
+        let agent_id = self
+            .ctx
+            .hero_store
+            .heroes
+            .iter()
+            .map(|x| x.agent_id)
+            .max()
+            .unwrap_or(0)
+            + 1;
+        let player = if is_owner {
+            self.ctx.player_id
+        } else {
+            1 - self.ctx.player_id
+        };
+        self.ctx.hero_store.push(Hero {
+            is_owner,
+            agent_id,
+            player,
+            soaking_power: 16,
+            shoot_cooldown: 1,
+            optimal_range: 4,
+            splash_bombs: 1,
+            position,
+            cooldown: 0,
+            wetness: 0,
+            alive: true,
+            role: HeroRole::Gunner,
+        });
+        self.selected = Some(agent_id);
+        self.ctx.sync();
+    }
+
+    /// Номер поля и знак кнопки (-1/+1), если кликнули по кнопке панели свойств.
+    fn panel_click(&self) -> Option<(usize, i32)> {
+        let hero = self.selected.and_then(|id| self.ctx.hero_store.get(id))?;
+        if !is_mouse_button_pressed(MouseButton::Left) {
+            return None;
+        }
+        let point = vec2(mouse_position().0, mouse_position().1);
+        property_buttons(hero, HERO_PANEL_Y, &FIELDS)
+            .into_iter()
+            .find_map(|(i, minus, plus)| {
+                if minus.contains(point) {
+                    Some((i, -1))
+                } else if plus.contains(point) {
+                    Some((i, 1))
+                } else {
+                    None
+                }
+            })
+    }
+
+    fn edit_field(&mut self, (field, sign): (usize, i32)) {
+        let Some(hero) = self.selected.and_then(|id| self.ctx.hero_store.get_mut(id)) else {
+            return;
+        };
+        let delta = STEPS[field] * sign;
+        match field {
+            0 => hero.wetness = (hero.wetness + delta).clamp(0, MAX_WETNESS),
+            1 => hero.cooldown = (hero.cooldown + delta).max(0),
+            _ => hero.splash_bombs = (hero.splash_bombs + delta).max(0),
+        }
+        self.ctx.sync();
+    }
+
+    /// Прогоняет ИИ и поиск укрытий на позиции, результат — в отладочные слои.
+    fn run_systems(&mut self) {
+        debug_overlay::clear();
+        find_cover_position(&self.ctx, 1 - self.ctx.player_id);
+
+        let actions = AggSystem::new().process(&self.ctx, &TurnClock::default());
+        for action in &actions {
+            let Some(hero) = self.ctx.hero_store.get(action.0) else {
+                continue;
+            };
+            for variant in &action.1 {
+                match variant {
+                    HeroActionVariant::Move(to) => {
+                        debug_overlay::arrow("editor", &hero.position, to, "#E5E5E5")
+                    }
+                    HeroActionVariant::Shoot { id } => {
+                        if let Some(target) = self.ctx.hero_store.get(*id) {
+                            debug_overlay::arrow(
+                                "editor",
+                                &hero.position,
+                                &target.position,
+                                "#E63946",
+                            );
+                        }
+                    }
+                    HeroActionVariant::Throw(to) => {
+                        debug_overlay::arrow("editor", &hero.position, to, "#FFA500")
+                    }
+                    HeroActionVariant::HunkerDown => {
+                        debug_overlay::text("editor", &hero.position, "H", "#FFFFFF")
+                    }
+                    HeroActionVariant::Message { .. } => {}
+                }
+            }
+        }
+        logger::log(&actions, "Editor::run_systems");
+    }
+
+    pub fn draw(&self) {
+        draw_text(
+            &format!("EDITOR [E] {}  F5 save  F9 load  G run", self.path),
+            10.0,
+            screen_height() - 40.0,
+            18.0,
+            Color::from_hex(0xFCA311),
+        );
+    }
+}
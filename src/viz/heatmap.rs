@@ -0,0 +1,143 @@
+use crate::{
+    core::{
+        heatmap_system::{HeatmapSystem, ScalarField},
+        opponent_model::OpponentModel,
+    },
+    data::{game_context::GameContext, hero::Hero},
+};
+
+use macroquad::prelude::*;
+
+/// Что показывает тепловая карта поверх тайлов.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HeatmapMode {
+    #[default]
+    Off,
+    Territory,
+    Threat,
+    Cover,
+    Distance,
+}
+
+impl HeatmapMode {
+    pub fn next(&self) -> HeatmapMode {
+        match self {
+            HeatmapMode::Off => HeatmapMode::Territory,
+            HeatmapMode::Territory => HeatmapMode::Threat,
+            HeatmapMode::Threat => HeatmapMode::Cover,
+            HeatmapMode::Cover => HeatmapMode::Distance,
+            HeatmapMode::Distance => HeatmapMode::Off,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HeatmapMode::Off => "off",
+            HeatmapMode::Territory => "territory",
+            HeatmapMode::Threat => "threat",
+            HeatmapMode::Cover => "cover",
+            HeatmapMode::Distance => "distance",
+        }
+    }
+
+    /// Поле для режима; для угрозы, укрытий и расстояния нужен выбранный герой.
+    pub fn field(
+        &self,
+        ctx: &GameContext,
+        opponents: &OpponentModel,
+        hero: Option<&Hero>,
+    ) -> Option<ScalarField> {
+        match (self, hero) {
+            (HeatmapMode::Off, _) => None,
+            (HeatmapMode::Territory, _) => Some(HeatmapSystem::territory(ctx)),
+            (HeatmapMode::Threat, Some(hero)) => Some(HeatmapSystem::threat(ctx, opponents, hero)),
+            (HeatmapMode::Cover, Some(hero)) => Some(HeatmapSystem::cover(ctx, hero)),
+            (HeatmapMode::Distance, Some(hero)) => Some(HeatmapSystem::distance(ctx, hero)),
+            (_, None) => None,
+        }
+    }
+}
+
+// от низкого значения к высокому: синий -> красный
+fn gradient(share: f32) -> Color {
+    Color::new(share, 0.2, 1.0 - share, 0.55)
+}
+
+fn territory_color(value: f32) -> Color {
+    if value > 0.0 {
+        Color::new(0.99, 0.64, 0.07, 0.45)
+    } else if value < 0.0 {
+        Color::new(0.47, 0.0, 0.0, 0.55)
+    } else {
+        Color::new(0.5, 0.5, 0.5, 0.3)
+    }
+}
+
+pub fn draw_heatmap(ctx: &GameContext, mode: HeatmapMode, field: &ScalarField) {
+    let tile_w = screen_width() / ctx.tilemap.get_width() as f32;
+    let tile_h = screen_height() / ctx.tilemap.get_height() as f32;
+
+    let (low, high) = field
+        .iter()
+        .flatten()
+        .fold((f32::MAX, f32::MIN), |(low, high), value| {
+            (low.min(*value), high.max(*value))
+        });
+
+    for tile in &ctx.tilemap.tiles {
+        let Some(value) = field[ctx.tilemap.to_index(&tile.position)] else {
+            continue;
+        };
+        let color = if mode == HeatmapMode::Territory {
+            territory_color(value)
+        } else if high > low {
+            gradient((value - low) / (high - low))
+        } else {
+            gradient(0.0)
+        };
+        draw_rectangle(
+            tile.position.x as f32 * tile_w,
+            tile.position.y as f32 * tile_h,
+            tile_w,
+            tile_h,
+            color,
+        );
+    }
+
+    draw_legend(mode, low, high);
+}
+
+// Легенда справа снизу: название режима и шкала
+fn draw_legend(mode: HeatmapMode, low: f32, high: f32) {
+    let x = screen_width() - 250.0;
+    let y = screen_height() - 90.0;
+
+    draw_rectangle(
+        x - 10.0,
+        y - 24.0,
+        240.0,
+        70.0,
+        Color::new(0.0, 0.0, 0.0, 0.7),
+    );
+    draw_text(&format!("heatmap: {} [H]", mode.name()), x, y, 18.0, WHITE);
+
+    if mode == HeatmapMode::Territory {
+        let items = [(1.0, "ours"), (-1.0, "theirs"), (0.0, "neutral")];
+        for (i, (value, label)) in items.iter().enumerate() {
+            let item_x = x + i as f32 * 75.0;
+            draw_rectangle(item_x, y + 10.0, 14.0, 14.0, territory_color(*value));
+            draw_text(label, item_x + 18.0, y + 22.0, 16.0, WHITE);
+        }
+        return;
+    }
+
+    let steps = 20;
+    for i in 0..steps {
+        let share = i as f32 / (steps - 1) as f32;
+        draw_rectangle(x + i as f32 * 10.0, y + 8.0, 10.0, 10.0, gradient(share));
+    }
+    if high >= low {
+        draw_text(&format!("{:.2}", low), x, y + 36.0, 16.0, WHITE);
+        draw_text(&format!("{:.2}", high), x + 160.0, y + 36.0, 16.0, WHITE);
+    }
+}
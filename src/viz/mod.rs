@@ -1,2 +1,12 @@
+// окно macroquad — только с фичей viewer; язык отрисовки нужен и без окна
+#[cfg(feature = "viewer")]
+pub mod app;
+#[cfg(feature = "viewer")]
+pub mod editor;
+#[cfg(feature = "viewer")]
+pub mod heatmap;
+#[cfg(feature = "viewer")]
 pub mod render;
 pub mod simple;
+#[cfg(feature = "viewer")]
+pub mod timeline;
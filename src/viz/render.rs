@@ -1,4 +1,22 @@
-use crate::data::{game_context::GameContext, position::Position, tile::TileType};
+use crate::{
+    core::{rationale::Rationale, shooter_system::ShooterSystem},
+    data::{
+        game_context::GameContext,
+        game_event::GameEvent,
+        hero::{HeroAction, HeroActionVariant, MAX_WETNESS},
+        position::Position,
+        tile::TileType,
+    },
+    infra::{
+        debug_overlay::{self, DebugOverlay, Shape},
+        logger::{self, Level},
+        pathfinder, snapshot,
+    },
+    viz::{
+        simple::{CoordMode, DrawingCommand, DrawingLine},
+        timeline::Timeline,
+    },
+};
 
 use macroquad::prelude::*;
 
@@ -42,17 +60,50 @@ fn color_convert<S: AsRef<str>>(s: S) -> Color {
     )
 }
 
-// Хелпер для рендера свойств справа
-fn draw_properties<T: std::fmt::Debug>(object: &T, start_y: f32, color: Color) {
+/// Верх панели свойств героя
+pub const HERO_PANEL_Y: f32 = 200.0;
+const PROPERTY_LINE: f32 = 20.0;
+
+// Хелпер для рендера свойств справа; у полей `editable` рисуются кнопки -/+
+fn draw_properties<T: std::fmt::Debug>(object: &T, start_y: f32, color: Color, editable: &[&str]) {
     let props = format!("{:#?}", object); // pretty debug формат
     let x = screen_width() - 250.0; // отступ от правого края
-    let line_height = 20.0;
 
     for (i, line) in props.lines().enumerate() {
-        draw_text(line, x, start_y + (i as f32) * line_height, 20.0, color);
+        draw_text(line, x, start_y + (i as f32) * PROPERTY_LINE, 20.0, color);
+    }
+    for (_, minus, plus) in property_buttons(object, start_y, editable) {
+        draw_rectangle_lines(minus.x, minus.y, minus.w, minus.h, 1.0, color);
+        draw_text("-", minus.x + 6.0, minus.y + 15.0, 20.0, color);
+        draw_rectangle_lines(plus.x, plus.y, plus.w, plus.h, 1.0, color);
+        draw_text("+", plus.x + 5.0, plus.y + 15.0, 20.0, color);
     }
 }
 
+/// Кнопки -/+ у строк панели свойств с полями `fields`: (номер поля в `fields`, минус, плюс).
+pub fn property_buttons<T: std::fmt::Debug>(
+    object: &T,
+    start_y: f32,
+    fields: &[&str],
+) -> Vec<(usize, Rect, Rect)> {
+    let x = screen_width() - 56.0;
+    format!("{:#?}", object)
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let name = line.trim_start().split(':').next()?;
+            let field = fields.iter().position(|x| *x == name)?;
+            // строка текста стоит на базовой линии, кнопка — вровень с ней
+            let y = start_y + i as f32 * PROPERTY_LINE - 15.0;
+            Some((
+                field,
+                Rect::new(x, y, 20.0, 20.0),
+                Rect::new(x + 26.0, y, 20.0, 20.0),
+            ))
+        })
+        .collect()
+}
+
 pub fn draw_map(ctx: &GameContext) {
     let tile_w = screen_width() / ctx.tilemap.get_width() as f32;
     let tile_h = screen_height() / ctx.tilemap.get_height() as f32;
@@ -126,6 +177,34 @@ pub fn draw_heroes(ctx: &GameContext) {
             WHITE,
         );
 
+        // мокрость полосой снизу, кулдаун и бомбы сверху
+        let (x, y) = (
+            hero.position.x as f32 * tile_w,
+            hero.position.y as f32 * tile_h,
+        );
+        let share = hero.wetness.clamp(0, MAX_WETNESS) as f32 / MAX_WETNESS as f32;
+        draw_rectangle(
+            x + 2.0,
+            y + tile_h - 6.0,
+            tile_w - 4.0,
+            4.0,
+            color_convert("#14213D"),
+        );
+        draw_rectangle(
+            x + 2.0,
+            y + tile_h - 6.0,
+            (tile_w - 4.0) * share,
+            4.0,
+            color_convert("#3CA7D5"),
+        );
+        draw_text(
+            &format!("c{} b{}", hero.cooldown, hero.splash_bombs),
+            x + 2.0,
+            y + 12.0,
+            14.0,
+            WHITE,
+        );
+
         let rec = Rect::new(
             hero.position.x as f32 * tile_w,
             hero.position.y as f32 * tile_h,
@@ -150,6 +229,21 @@ fn draw_tile_text(
     draw_text(text, x, y, font_size, color);
 }
 
+/// Клетка под курсором мыши.
+pub fn tile_under_mouse(ctx: &GameContext) -> Option<Position> {
+    let tile_w = screen_width() / ctx.tilemap.get_width() as f32;
+    let tile_h = screen_height() / ctx.tilemap.get_height() as f32;
+    let (x, y) = mouse_position();
+    let (x, y) = ((x / tile_w).floor() as i32, (y / tile_h).floor() as i32);
+    if ctx.tilemap.out_of_bounds(x, y) {
+        return None;
+    }
+    Some(Position {
+        x: x as usize,
+        y: y as usize,
+    })
+}
+
 pub fn debug_position<S: AsRef<str>, U: AsRef<str>>(
     ctx: &GameContext,
     position: &Position,
@@ -171,10 +265,17 @@ pub fn debug_position<S: AsRef<str>, U: AsRef<str>>(
     draw_tile_text(meta.as_ref(), position, tile_w, tile_h, 20.0, BLACK);
 }
 
-pub fn render_context(ctx: &GameContext) {
+/// Панели свойств под курсором. `editable` — герой из редактора и его изменяемые поля:
+/// его панель видна всегда и заменяет панель героя под курсором.
+pub fn render_context(
+    ctx: &GameContext,
+    rationale: &[Rationale],
+    editable: Option<(i32, &[&str])>,
+) {
     let tile_w = screen_width() / ctx.tilemap.get_width() as f32;
     let tile_h = screen_height() / ctx.tilemap.get_height() as f32;
     let mouse_point = vec2(mouse_position().0, mouse_position().1);
+    let edited = editable.and_then(|(id, fields)| Some((ctx.hero_store.get(id)?, fields)));
 
     for tile in &ctx.tilemap.tiles {
         let position = &tile.position;
@@ -185,17 +286,465 @@ pub fn render_context(ctx: &GameContext) {
             tile_w,
             tile_h,
         );
-        let hero = ctx
-            .hero_store
-            .heroes
-            .iter()
-            .find(|x| x.position == tile.position);
+        let hero = ctx.hero_store.alive().find(|x| x.position == tile.position);
 
         if rec.contains(mouse_point) {
-            if let Some(h) = hero {
-                draw_properties(h, 200.0, WHITE);
+            if let Some(h) = hero.filter(|_| edited.is_none()) {
+                draw_properties(h, HERO_PANEL_Y, WHITE, &[]);
+                if let Some(reason) = rationale.iter().find(|x| x.agent_id == h.agent_id) {
+                    draw_rationale(reason);
+                }
+            }
+            draw_properties(tile, 20.0, WHITE, &[]);
+        }
+    }
+
+    if let Some((hero, fields)) = edited {
+        draw_properties(hero, HERO_PANEL_Y, WHITE, fields);
+    }
+}
+
+// Обоснование решения героя под курсором: система, режим, входы режима
+// и варианты по убыванию оценки с разбивкой по признакам
+fn draw_rationale(reason: &Rationale) {
+    let format_features = |features: &[(&str, f32)]| {
+        features
+            .iter()
+            .map(|(name, value)| format!("{} {:.1}", name, value))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let mut lines = vec![
+        (
+            format!("{}: {:?}", reason.system, reason.state),
+            color_convert("#FCA311"),
+        ),
+        (format_features(&reason.inputs), WHITE),
+    ];
+    let mut candidates: Vec<_> = reason.candidates.iter().collect();
+    candidates.sort_by(|a, b| b.score().total_cmp(&a.score()));
+    for candidate in candidates {
+        let action = HeroAction(reason.agent_id, candidate.actions.clone()).to_string();
+        let action = action.split_once(';').map_or("", |(_, x)| x);
+        lines.push((
+            format!(
+                "{} {} = {:.1} ({})",
+                if candidate.chosen { "*" } else { " " },
+                action,
+                candidate.score(),
+                format_features(&candidate.features)
+            ),
+            if candidate.chosen {
+                WHITE
+            } else {
+                color_convert("#A8A8A8")
+            },
+        ));
+    }
+
+    let (x, y, line_height) = (10.0, 220.0, 18.0);
+    let width = lines
+        .iter()
+        .map(|(text, _)| measure_text(text, None, 18, 1.0).width)
+        .fold(0.0, f32::max);
+    draw_rectangle(
+        x - 4.0,
+        y - line_height,
+        width + 8.0,
+        lines.len() as f32 * line_height + 8.0,
+        Color::new(0.0, 0.0, 0.0, 0.7),
+    );
+    for (i, (text, color)) in lines.iter().enumerate() {
+        draw_text(text, x, y + i as f32 * line_height, 18.0, *color);
+    }
+}
+
+// Последние записи лога поверх карты, снизу слева
+pub fn draw_log_panel(count: usize) {
+    let line_height = 18.0;
+    let records = logger::recent(count);
+    let start_y = screen_height() - records.len() as f32 * line_height;
+
+    draw_rectangle(
+        0.0,
+        start_y - line_height,
+        screen_width(),
+        records.len() as f32 * line_height + line_height,
+        Color::new(0.0, 0.0, 0.0, 0.7),
+    );
+
+    for (i, record) in records.iter().enumerate() {
+        let color = match record.level {
+            Level::Error => color_convert("#E63946"),
+            Level::Warn => color_convert("#FCA311"),
+            Level::Info => WHITE,
+            _ => color_convert("#A8A8A8"),
+        };
+        draw_text(
+            &format!("[{}] [{}] {}", record.turn, record.target, record.message),
+            10.0,
+            start_y + i as f32 * line_height,
+            18.0,
+            color,
+        );
+    }
+}
+
+// События последнего хода: перемещения линиями, бомбы и выбывания кругами
+pub fn draw_events(ctx: &GameContext, events: &[GameEvent]) {
+    let tile_w = screen_width() / ctx.tilemap.get_width() as f32;
+    let tile_h = screen_height() / ctx.tilemap.get_height() as f32;
+    let center = |position: &Position| {
+        vec2(
+            position.x as f32 * tile_w + tile_w / 2.0,
+            position.y as f32 * tile_h + tile_h / 2.0,
+        )
+    };
+
+    for event in events {
+        match event {
+            GameEvent::HeroMoved { from, to, .. } => {
+                let (from, to) = (center(from), center(to));
+                draw_line(from.x, from.y, to.x, to.y, 2.0, color_convert("#E5E5E5"));
+            }
+            GameEvent::BombLanded {
+                position: Some(position),
+                ..
+            } => {
+                let point = center(position);
+                draw_circle_lines(
+                    point.x,
+                    point.y,
+                    tile_w.max(tile_h) * 1.5,
+                    3.0,
+                    color_convert("#FFA500"),
+                );
+            }
+            GameEvent::HeroEliminated { position, .. } => {
+                let point = center(position);
+                draw_circle_lines(
+                    point.x,
+                    point.y,
+                    tile_w.min(tile_h) * 0.4,
+                    3.0,
+                    color_convert("#E63946"),
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+// Отладочные слои систем поверх карты и список слоёв с номерами клавиш;
+// видимость берётся из текущего буфера, чтобы переключение работало и на прошлых ходах
+pub fn draw_overlay(ctx: &GameContext, overlay: &DebugOverlay) {
+    let tile_w = screen_width() / ctx.tilemap.get_width() as f32;
+    let tile_h = screen_height() / ctx.tilemap.get_height() as f32;
+    let center = |position: &Position| {
+        vec2(
+            position.x as f32 * tile_w + tile_w / 2.0,
+            position.y as f32 * tile_h + tile_h / 2.0,
+        )
+    };
+    for layer in overlay
+        .layers()
+        .iter()
+        .filter(|x| debug_overlay::is_visible(&x.name))
+    {
+        let heat = layer.shapes.iter().filter_map(|shape| match shape {
+            Shape::Heat { value, .. } => Some(*value),
+            _ => None,
+        });
+        let (low, high) = heat.fold((f32::MAX, f32::MIN), |(low, high), value| {
+            (low.min(value), high.max(value))
+        });
+
+        for shape in &layer.shapes {
+            match shape {
+                Shape::Tile { position, color } => {
+                    let mut color = color_convert(color);
+                    color.a = 0.5;
+                    draw_rectangle(
+                        position.x as f32 * tile_w,
+                        position.y as f32 * tile_h,
+                        tile_w,
+                        tile_h,
+                        color,
+                    );
+                }
+                Shape::Arrow { from, to, color } => {
+                    let (from, to) = (center(from), center(to));
+                    let color = color_convert(color);
+                    draw_line(from.x, from.y, to.x, to.y, 2.0, color);
+                    draw_circle(to.x, to.y, tile_w.min(tile_h) * 0.12, color);
+                }
+                Shape::Path { points, color } => {
+                    let color = color_convert(color);
+                    for pair in points.windows(2) {
+                        let (from, to) = (center(&pair[0]), center(&pair[1]));
+                        draw_line(from.x, from.y, to.x, to.y, 2.0, color);
+                    }
+                }
+                Shape::Text {
+                    position,
+                    text,
+                    color,
+                } => {
+                    draw_tile_text(text, position, tile_w, tile_h, 18.0, color_convert(color));
+                }
+                Shape::Heat { position, value } => {
+                    let share = if high > low {
+                        (value - low) / (high - low)
+                    } else {
+                        1.0
+                    };
+                    draw_rectangle(
+                        position.x as f32 * tile_w,
+                        position.y as f32 * tile_h,
+                        tile_w,
+                        tile_h,
+                        Color::new(1.0, 0.0, 0.0, 0.1 + share * 0.5),
+                    );
+                }
+            }
+        }
+    }
+
+    for (i, layer) in overlay.layers().iter().enumerate() {
+        draw_text(
+            &format!("{}: {}", i + 1, layer.name),
+            10.0,
+            20.0 + i as f32 * 18.0,
+            18.0,
+            if debug_overlay::is_visible(&layer.name) {
+                WHITE
+            } else {
+                color_convert("#A8A8A8")
+            },
+        );
+    }
+}
+
+const SCRUBBER_HEIGHT: f32 = 14.0;
+
+fn scrubber_rect() -> Rect {
+    Rect::new(
+        10.0,
+        screen_height() - SCRUBBER_HEIGHT - 6.0,
+        screen_width() - 20.0,
+        SCRUBBER_HEIGHT,
+    )
+}
+
+// Полоса ходов внизу экрана: курсор, скорость и пауза
+pub fn draw_timeline(timeline: &Timeline) {
+    let rec = scrubber_rect();
+    let cell = rec.w / timeline.frames().len().max(1) as f32;
+
+    draw_rectangle(rec.x, rec.y, rec.w, rec.h, Color::new(0.0, 0.0, 0.0, 0.7));
+    draw_rectangle(
+        rec.x + timeline.cursor() as f32 * cell,
+        rec.y,
+        cell.max(2.0),
+        rec.h,
+        color_convert("#FCA311"),
+    );
+    draw_rectangle_lines(rec.x, rec.y, rec.w, rec.h, 1.0, WHITE);
+
+    draw_text(
+        &format!(
+            "turn {} [{}/{}] x{} {}",
+            timeline.current().map_or(0, |x| x.turn),
+            timeline.cursor() + 1,
+            timeline.frames().len(),
+            timeline.speed(),
+            if timeline.is_paused() { "paused" } else { "" }
+        ),
+        rec.x,
+        rec.y - 4.0,
+        18.0,
+        WHITE,
+    );
+}
+
+/// Номер хода под мышью, если кнопка зажата над полосой ходов.
+pub fn scrubber_seek(count: usize) -> Option<usize> {
+    let rec = scrubber_rect();
+    let (x, y) = mouse_position();
+    if count == 0 || !is_mouse_button_down(MouseButton::Left) || !rec.contains(vec2(x, y)) {
+        return None;
+    }
+    Some(((x - rec.x) / rec.w * count as f32) as usize)
+}
+
+// Решения героев за ход: путь, выстрел с ожидаемым уроном, область бомбы,
+// щит за HUNKER_DOWN и облачко с сообщением
+pub fn draw_actions(ctx: &GameContext, actions: &[HeroAction]) {
+    let tile_w = screen_width() / ctx.tilemap.get_width() as f32;
+    let tile_h = screen_height() / ctx.tilemap.get_height() as f32;
+    let center = |position: &Position| {
+        vec2(
+            position.x as f32 * tile_w + tile_w / 2.0,
+            position.y as f32 * tile_h + tile_h / 2.0,
+        )
+    };
+
+    for HeroAction(agent_id, variants) in actions {
+        let Some(hero) = ctx.hero_store.get(*agent_id) else {
+            continue;
+        };
+        let from = center(&hero.position);
+
+        for variant in variants {
+            match variant {
+                HeroActionVariant::Move(target) => {
+                    let path = pathfinder::find_path(ctx, &hero.position, target)
+                        .unwrap_or_else(|| vec![hero.position, *target]);
+                    let color = color_convert("#E5E5E5");
+                    for pair in path.windows(2) {
+                        let (a, b) = (center(&pair[0]), center(&pair[1]));
+                        draw_line(a.x, a.y, b.x, b.y, 2.0, color);
+                    }
+                    let end = center(target);
+                    draw_circle(end.x, end.y, tile_w.min(tile_h) * 0.12, color);
+                }
+                HeroActionVariant::Shoot { id } => {
+                    let Some(target) = ctx.hero_store.get(*id) else {
+                        continue;
+                    };
+                    let to = center(&target.position);
+                    let color = color_convert("#E63946");
+                    draw_line(from.x, from.y, to.x, to.y, 2.0, color);
+                    let damage = ShooterSystem::expected_damage(ctx, hero, target);
+                    draw_text(
+                        &format!("-{}", damage),
+                        (from.x + to.x) / 2.0,
+                        (from.y + to.y) / 2.0,
+                        18.0,
+                        color,
+                    );
+                }
+                HeroActionVariant::Throw(target) => {
+                    let mut color = color_convert("#FFA500");
+                    color.a = 0.35;
+                    draw_rectangle(
+                        (target.x as f32 - 1.0) * tile_w,
+                        (target.y as f32 - 1.0) * tile_h,
+                        tile_w * 3.0,
+                        tile_h * 3.0,
+                        color,
+                    );
+                    let to = center(target);
+                    draw_line(from.x, from.y, to.x, to.y, 1.0, color_convert("#FFA500"));
+                }
+                HeroActionVariant::HunkerDown => {
+                    // щит в правом верхнем углу клетки
+                    let (x, y) = (from.x + tile_w * 0.25, from.y - tile_h * 0.4);
+                    let size = tile_w.min(tile_h) * 0.2;
+                    let color = color_convert("#C0C0C0");
+                    draw_rectangle(x - size / 2.0, y, size, size * 0.6, color);
+                    draw_triangle(
+                        vec2(x - size / 2.0, y + size * 0.6),
+                        vec2(x + size / 2.0, y + size * 0.6),
+                        vec2(x, y + size * 1.2),
+                        color,
+                    );
+                }
+                HeroActionVariant::Message { text } => {
+                    let size = measure_text(text, None, 16, 1.0);
+                    let (x, y) = (from.x + 6.0, from.y - tile_h * 0.5 - size.height - 8.0);
+                    draw_rectangle(x - 4.0, y - 4.0, size.width + 8.0, size.height + 8.0, WHITE);
+                    draw_triangle(
+                        vec2(x, y + size.height + 4.0),
+                        vec2(x + 8.0, y + size.height + 4.0),
+                        vec2(from.x, from.y - tile_h * 0.3),
+                        WHITE,
+                    );
+                    draw_text(text, x, y + size.height, 16.0, BLACK);
+                }
+            }
+        }
+    }
+}
+
+// Файл языка отрисовки поверх карты; клеточные координаты — в клетках окна
+pub fn draw_commands(ctx: &GameContext, lines: &[DrawingLine]) {
+    let tile_w = screen_width() / ctx.tilemap.get_width() as f32;
+    let tile_h = screen_height() / ctx.tilemap.get_height() as f32;
+    let color = |color: &str| {
+        let [r, g, b, a] = snapshot::parse_color(color);
+        Color::from_rgba(r, g, b, a)
+    };
+
+    // MODE действует по порядку строк, поэтому масштаб определяется до сортировки по слоям
+    let mut scale = (1.0, 1.0);
+    let mut resolved = vec![];
+    for line in lines {
+        match line.command {
+            DrawingCommand::Mode(CoordMode::Pixel) => scale = (1.0, 1.0),
+            DrawingCommand::Mode(CoordMode::Tile(_)) => scale = (tile_w, tile_h),
+            _ => resolved.push((line.layer, scale, &line.command)),
+        }
+    }
+    resolved.sort_by_key(|(layer, _, _)| *layer);
+
+    for (_, (sx, sy), command) in resolved {
+        match command {
+            DrawingCommand::Clear { color: c } => {
+                draw_rectangle(0.0, 0.0, screen_width(), screen_height(), color(c))
+            }
+            DrawingCommand::Mode(_) => {}
+            DrawingCommand::Rect {
+                x,
+                y,
+                w,
+                h,
+                color: c,
+            } => draw_rectangle(x * sx, y * sy, w * sx, h * sy, color(c)),
+            DrawingCommand::Circle { x, y, r, color: c } => {
+                draw_circle(x * sx, y * sy, r * sx.min(sy), color(c))
+            }
+            DrawingCommand::Line {
+                x1,
+                y1,
+                x2,
+                y2,
+                color: c,
+                width,
+            } => draw_line(x1 * sx, y1 * sy, x2 * sx, y2 * sy, *width as f32, color(c)),
+            DrawingCommand::Polyline {
+                points,
+                color: c,
+                width,
+            } => {
+                for pair in points.windows(2) {
+                    let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+                    draw_line(x1 * sx, y1 * sy, x2 * sx, y2 * sy, *width as f32, color(c));
+                }
+            }
+            // веером треугольников: верно для выпуклых многоугольников
+            DrawingCommand::Polygon { points, color: c } => {
+                if let Some(((x0, y0), rest)) = points.split_first() {
+                    for pair in rest.windows(2) {
+                        let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+                        draw_triangle(
+                            vec2(x0 * sx, y0 * sy),
+                            vec2(x1 * sx, y1 * sy),
+                            vec2(x2 * sx, y2 * sy),
+                            color(c),
+                        );
+                    }
+                }
+            }
+            DrawingCommand::Text {
+                x,
+                y,
+                text,
+                color: c,
+            } => {
+                draw_text(text, x * sx, y * sy, 18.0, color(c));
             }
-            draw_properties(tile, 20.0, WHITE);
         }
     }
 }
@@ -1,45 +1,83 @@
-use std::fmt;
+use std::{error::Error, fmt, fs, str::FromStr};
 
-use crate::data::position::Position;
+use crate::{
+    data::{game_context::GameContext, hero::MAX_WETNESS, position::Position},
+    infra::{
+        debug_overlay::{DebugOverlay, Shape},
+        snapshot::{self, TILE_SIZE},
+    },
+};
+
+/// Как понимать координаты следующих команд.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CoordMode {
+    Pixel,
+    /// Координаты и размеры в клетках заданного размера (пикселей)
+    Tile(f32),
+}
 
-#[derive(Debug)]
+/// Команда текстового языка отрисовки (`public/index.html`).
+///
+/// Строка: `<layer> <command>`, слои рисуются по возрастанию номера.
+/// `MODE` действует на все следующие строки файла, `#` — комментарий.
+#[derive(Debug, Clone, PartialEq)]
 pub enum DrawingCommand {
     Clear {
         color: String,
     },
+    Mode(CoordMode),
     Rect {
-        x: i32,
-        y: i32,
-        w: i32,
-        h: i32,
+        x: f32,
+        y: f32,
+        w: f32,
+        h: f32,
         color: String,
     },
     Circle {
-        x: i32,
-        y: i32,
-        r: i32,
+        x: f32,
+        y: f32,
+        r: f32,
         color: String,
     },
     Line {
-        x1: i32,
-        y1: i32,
-        x2: i32,
-        y2: i32,
+        x1: f32,
+        y1: f32,
+        x2: f32,
+        y2: f32,
+        color: String,
+        width: u32,
+    },
+    Polyline {
+        points: Vec<(f32, f32)>,
         color: String,
         width: u32,
     },
+    /// Закрашенный многоугольник
+    Polygon {
+        points: Vec<(f32, f32)>,
+        color: String,
+    },
     Text {
-        x: i32,
-        y: i32,
+        x: f32,
+        y: f32,
         text: String,
         color: String,
     },
 }
 
+fn write_points(f: &mut fmt::Formatter<'_>, points: &[(f32, f32)]) -> fmt::Result {
+    for (x, y) in points {
+        write!(f, " {} {}", x, y)?;
+    }
+    Ok(())
+}
+
 impl fmt::Display for DrawingCommand {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             DrawingCommand::Clear { color } => write!(f, "CLEAR {}", color),
+            DrawingCommand::Mode(CoordMode::Pixel) => write!(f, "MODE PIXEL"),
+            DrawingCommand::Mode(CoordMode::Tile(size)) => write!(f, "MODE TILE {}", size),
             DrawingCommand::Rect { x, y, w, h, color } => {
                 write!(f, "DRAW RECT {} {} {} {} {}", x, y, w, h, color)
             }
@@ -58,11 +96,350 @@ impl fmt::Display for DrawingCommand {
                 "DRAW LINE {} {} {} {} {} {}",
                 x1, y1, x2, y2, color, width
             ),
+            DrawingCommand::Polyline {
+                points,
+                color,
+                width,
+            } => {
+                write!(f, "DRAW POLYLINE {} {}", color, width)?;
+                write_points(f, points)
+            }
+            DrawingCommand::Polygon { points, color } => {
+                write!(f, "DRAW POLYGON {}", color)?;
+                write_points(f, points)
+            }
+            // кавычки внутри текста грамматика не поддерживает
             DrawingCommand::Text { x, y, text, color } => {
-                write!(f, "TEXT {} {} \"{}\" {}", x, y, text, color)
+                write!(
+                    f,
+                    "TEXT {} {} \"{}\" {}",
+                    x,
+                    y,
+                    text.replace('"', "'"),
+                    color
+                )
+            }
+        }
+    }
+}
+
+/// Разбивает строку по пробелам; текст в кавычках — один токен (без кавычек).
+fn tokenize(line: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut tokens = vec![];
+    let mut rest = line.trim();
+    while !rest.is_empty() {
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"').ok_or("unterminated string")?;
+            tokens.push(quoted[..end].to_string());
+            rest = quoted[end + 1..].trim_start();
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            tokens.push(rest[..end].to_string());
+            rest = rest[end..].trim_start();
+        }
+    }
+    Ok(tokens)
+}
+
+fn number(tokens: &[String], index: usize) -> Result<f32, Box<dyn Error>> {
+    let token = tokens
+        .get(index)
+        .ok_or_else(|| format!("missing argument {}", index + 1))?;
+    Ok(token
+        .parse::<f32>()
+        .map_err(|_| format!("bad number {}", token))?)
+}
+
+fn word(tokens: &[String], index: usize) -> Result<String, Box<dyn Error>> {
+    Ok(tokens
+        .get(index)
+        .ok_or_else(|| format!("missing argument {}", index + 1))?
+        .clone())
+}
+
+fn points(tokens: &[String]) -> Result<Vec<(f32, f32)>, Box<dyn Error>> {
+    if !tokens.len().is_multiple_of(2) {
+        return Err("odd number of coordinates".into());
+    }
+    (0..tokens.len() / 2)
+        .map(|i| Ok((number(tokens, i * 2)?, number(tokens, i * 2 + 1)?)))
+        .collect()
+}
+
+impl FromStr for DrawingCommand {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(s)?;
+        let head = tokens.first().ok_or("empty command")?.to_uppercase();
+        let (name, args) = if head == "DRAW" {
+            (
+                format!("DRAW {}", word(&tokens, 1)?.to_uppercase()),
+                &tokens[2..],
+            )
+        } else {
+            (head, &tokens[1..])
+        };
+
+        let command = match name.as_str() {
+            "CLEAR" => DrawingCommand::Clear {
+                color: word(args, 0)?,
+            },
+            "MODE" => match word(args, 0)?.to_uppercase().as_str() {
+                "PIXEL" => DrawingCommand::Mode(CoordMode::Pixel),
+                "TILE" => DrawingCommand::Mode(CoordMode::Tile(number(args, 1)?)),
+                other => return Err(format!("unknown mode {}", other).into()),
+            },
+            "DRAW RECT" => DrawingCommand::Rect {
+                x: number(args, 0)?,
+                y: number(args, 1)?,
+                w: number(args, 2)?,
+                h: number(args, 3)?,
+                color: word(args, 4)?,
+            },
+            "DRAW CIRCLE" => DrawingCommand::Circle {
+                x: number(args, 0)?,
+                y: number(args, 1)?,
+                r: number(args, 2)?,
+                color: word(args, 3)?,
+            },
+            "DRAW LINE" => DrawingCommand::Line {
+                x1: number(args, 0)?,
+                y1: number(args, 1)?,
+                x2: number(args, 2)?,
+                y2: number(args, 3)?,
+                color: word(args, 4)?,
+                width: args.get(5).map_or(Ok(1), |x| x.parse())?,
+            },
+            "DRAW POLYLINE" => DrawingCommand::Polyline {
+                color: word(args, 0)?,
+                width: word(args, 1)?.parse()?,
+                points: points(&args[2..])?,
+            },
+            "DRAW POLYGON" => DrawingCommand::Polygon {
+                color: word(args, 0)?,
+                points: points(args.get(1..).unwrap_or_default())?,
+            },
+            "TEXT" => DrawingCommand::Text {
+                x: number(args, 0)?,
+                y: number(args, 1)?,
+                text: word(args, 2)?,
+                color: word(args, 3)?,
+            },
+            other => return Err(format!("unknown command {}", other).into()),
+        };
+        Ok(command)
+    }
+}
+
+/// Строка файла: номер слоя и команда.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrawingLine {
+    pub layer: u32,
+    pub command: DrawingCommand,
+}
+
+impl fmt::Display for DrawingLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.layer, self.command)
+    }
+}
+
+impl FromStr for DrawingLine {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (layer, command) = s.split_once(char::is_whitespace).ok_or("missing command")?;
+        Ok(DrawingLine {
+            layer: layer.parse().map_err(|_| format!("bad layer {}", layer))?,
+            command: command.parse()?,
+        })
+    }
+}
+
+/// Разбирает файл целиком; пустые строки и `#`-комментарии пропускаются.
+pub fn parse(text: &str) -> Result<Vec<DrawingLine>, Box<dyn Error>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(i, line)| {
+            line.parse::<DrawingLine>()
+                .map_err(|err| format!("line {}: {}", i + 1, err).into())
+        })
+        .collect()
+}
+
+pub fn write(lines: &[DrawingLine]) -> String {
+    lines.iter().map(|x| format!("{}\n", x)).collect()
+}
+
+// Цвет с альфой для canvas ("#RRGGBBAA")
+fn alpha(color: &str, alpha: f32) -> String {
+    let [r, g, b, _] = snapshot::parse_color(color);
+    format!(
+        "#{:02X}{:02X}{:02X}{:02X}",
+        r,
+        g,
+        b,
+        (alpha.clamp(0.0, 1.0) * 255.0) as u8
+    )
+}
+
+fn center(position: &Position) -> (f32, f32) {
+    (position.x as f32 + 0.5, position.y as f32 + 0.5)
+}
+
+/// Ход целиком в клеточных координатах: слой 0 — карта и герои,
+/// слои отладочного буфера идут следом в порядке появления.
+pub fn from_overlay(ctx: &GameContext, overlay: &DebugOverlay) -> Vec<DrawingLine> {
+    let mut lines = vec![];
+    let mut push = |layer: u32, command: DrawingCommand| lines.push(DrawingLine { layer, command });
+
+    push(0, DrawingCommand::Mode(CoordMode::Tile(TILE_SIZE as f32)));
+    push(
+        0,
+        DrawingCommand::Clear {
+            color: snapshot::BACKGROUND.to_string(),
+        },
+    );
+    for tile in &ctx.tilemap.tiles {
+        push(
+            0,
+            DrawingCommand::Rect {
+                x: tile.position.x as f32,
+                y: tile.position.y as f32,
+                w: 1.0,
+                h: 1.0,
+                color: snapshot::tile_color(tile.tile_type).to_string(),
+            },
+        );
+    }
+    for hero in ctx.hero_store.alive() {
+        let (x, y) = center(&hero.position);
+        let color = if hero.is_owner {
+            snapshot::MY_HERO
+        } else {
+            snapshot::ENEMY_HERO
+        };
+        push(
+            0,
+            DrawingCommand::Circle {
+                x,
+                y,
+                r: 0.33,
+                color: color.to_string(),
+            },
+        );
+        let share = hero.wetness.clamp(0, MAX_WETNESS) as f32 / MAX_WETNESS as f32;
+        push(
+            0,
+            DrawingCommand::Rect {
+                x: x - 0.4,
+                y: y + 0.3,
+                w: 0.8 * share,
+                h: 0.12,
+                color: snapshot::WETNESS.to_string(),
+            },
+        );
+        push(
+            0,
+            DrawingCommand::Text {
+                x: x - 0.15,
+                y: y + 0.15,
+                text: hero.agent_id.to_string(),
+                color: "#FFFFFF".to_string(),
+            },
+        );
+    }
+
+    for (i, layer) in overlay.layers().iter().enumerate() {
+        let index = i as u32 + 1;
+        let range = snapshot::heat_range(&layer.shapes);
+        for shape in &layer.shapes {
+            match shape {
+                Shape::Tile { position, color } => push(
+                    index,
+                    DrawingCommand::Rect {
+                        x: position.x as f32,
+                        y: position.y as f32,
+                        w: 1.0,
+                        h: 1.0,
+                        color: alpha(color, 0.5),
+                    },
+                ),
+                Shape::Arrow { from, to, color } => {
+                    let ((x1, y1), (x2, y2)) = (center(from), center(to));
+                    push(
+                        index,
+                        DrawingCommand::Line {
+                            x1,
+                            y1,
+                            x2,
+                            y2,
+                            color: color.clone(),
+                            width: 2,
+                        },
+                    );
+                    push(
+                        index,
+                        DrawingCommand::Circle {
+                            x: x2,
+                            y: y2,
+                            r: 0.12,
+                            color: color.clone(),
+                        },
+                    );
+                }
+                Shape::Path { points, color } => push(
+                    index,
+                    DrawingCommand::Polyline {
+                        points: points.iter().map(center).collect(),
+                        color: color.clone(),
+                        width: 2,
+                    },
+                ),
+                Shape::Text {
+                    position,
+                    text,
+                    color,
+                } => push(
+                    index,
+                    DrawingCommand::Text {
+                        x: position.x as f32 + 0.1,
+                        y: position.y as f32 + 0.6,
+                        text: text.clone(),
+                        color: color.clone(),
+                    },
+                ),
+                Shape::Heat { position, value } => push(
+                    index,
+                    DrawingCommand::Rect {
+                        x: position.x as f32,
+                        y: position.y as f32,
+                        w: 1.0,
+                        h: 1.0,
+                        color: alpha(
+                            snapshot::HEAT,
+                            0.1 + snapshot::heat_share(*value, range) * 0.5,
+                        ),
+                    },
+                ),
             }
         }
     }
+    lines
+}
+
+/// Файл для `public/index.html`; имена слоёв — в комментариях.
+pub fn dump(ctx: &GameContext, overlay: &DebugOverlay, path: &str) -> Result<(), Box<dyn Error>> {
+    let mut text = String::from("# 0 map\n");
+    for (i, layer) in overlay.layers().iter().enumerate() {
+        text.push_str(&format!("# {} {}\n", i + 1, layer.name));
+    }
+    text.push_str(&write(&from_overlay(ctx, overlay)));
+    fs::write(path, text)?;
+    Ok(())
 }
 
 pub fn viz_simple_debug<S: AsRef<str>, R: AsRef<str>>(
@@ -71,17 +448,35 @@ pub fn viz_simple_debug<S: AsRef<str>, R: AsRef<str>>(
     color: R,
     text: Option<String>,
 ) {
-    let cmd_type = command_type.as_ref().to_uppercase();
-    let color = color.as_ref();
-
-    let output = match cmd_type.as_str() {
-        "RECT" | "CIRCLE" => format!("DRAW {} {} {} {}", cmd_type, position.x, position.y, color),
-        "TEXT" => {
-            let t = text.unwrap_or_default();
-            format!("TEXT {} {} \"{}\" {}", position.x, position.y, t, color)
+    let color = color.as_ref().to_string();
+    let size = TILE_SIZE as f32;
+    let (x, y) = (position.x as f32 * size, position.y as f32 * size);
+
+    let command = match command_type.as_ref().to_uppercase().as_str() {
+        "RECT" => DrawingCommand::Rect {
+            x,
+            y,
+            w: size,
+            h: size,
+            color,
+        },
+        "CIRCLE" => DrawingCommand::Circle {
+            x: x + size / 2.0,
+            y: y + size / 2.0,
+            r: size / 3.0,
+            color,
+        },
+        "TEXT" => DrawingCommand::Text {
+            x,
+            y: y + size / 2.0,
+            text: text.unwrap_or_default(),
+            color,
+        },
+        other => {
+            eprintln!("# unknown command {} {} {}", other, position.x, position.y);
+            return;
         }
-        _ => format!("UNKNOWN {} {} {}", cmd_type, position.x, position.y),
     };
 
-    eprintln!("{}", output);
+    eprintln!("{}", DrawingLine { layer: 0, command });
 }
@@ -0,0 +1,127 @@
+use crate::{
+    core::rationale::Rationale,
+    data::{game_context::GameContext, game_event::GameEvent, hero::HeroAction},
+    infra::debug_overlay::DebugOverlay,
+};
+
+/// Скорость по умолчанию, ходов в секунду
+pub const DEFAULT_SPEED: f32 = 1.0;
+pub const MIN_SPEED: f32 = 0.25;
+pub const MAX_SPEED: f32 = 16.0;
+
+/// Снимок хода в момент принятия решения.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub turn: u32,
+    pub ctx: GameContext,
+    pub overlay: DebugOverlay,
+    pub events: Vec<GameEvent>,
+    /// Команды, отданные героям в этом ходу
+    pub actions: Vec<HeroAction>,
+    /// Почему герои получили эти команды
+    pub rationale: Vec<Rationale>,
+}
+
+/// История ходов визуализатора: пауза, шаги назад и вперёд, скорость.
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    frames: Vec<Frame>,
+    cursor: usize,
+    paused: bool,
+    speed: f32,
+    ticker: f32,
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Self {
+            frames: vec![],
+            cursor: 0,
+            paused: false,
+            speed: DEFAULT_SPEED,
+            ticker: 0.0,
+        }
+    }
+
+    /// Добавляет новый ход; если смотрели последний ход, курсор переходит на новый.
+    pub fn push(&mut self, frame: Frame) {
+        let follow = self.is_live();
+        self.frames.push(frame);
+        if follow {
+            self.cursor = self.frames.len() - 1;
+        }
+    }
+
+    pub fn current(&self) -> Option<&Frame> {
+        self.frames.get(self.cursor)
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Курсор на последнем ходе: следующий шаг читает новый ход из ввода.
+    pub fn is_live(&self) -> bool {
+        self.cursor + 1 >= self.frames.len()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed * 2.0).min(MAX_SPEED);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = (self.speed / 2.0).max(MIN_SPEED);
+    }
+
+    pub fn step_back(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    /// Шаг по истории; false, если истории дальше нет и нужен новый ход.
+    pub fn step_forward(&mut self) -> bool {
+        if self.is_live() {
+            return false;
+        }
+        self.cursor += 1;
+        true
+    }
+
+    pub fn seek(&mut self, index: usize) {
+        self.cursor = index.min(self.frames.len().saturating_sub(1));
+    }
+
+    /// Продвигает часы воспроизведения; true, когда пора сделать шаг вперёд.
+    pub fn tick(&mut self, dt: f32) -> bool {
+        if self.paused {
+            return false;
+        }
+        self.ticker += dt * self.speed;
+        if self.ticker < 1.0 {
+            return false;
+        }
+        self.ticker -= 1.0;
+        true
+    }
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}